@@ -12,6 +12,7 @@ const url = "http://0.0.0.0:9933";
 describe("e2e", () => {
   let client: PolywrapClient;
   const uri = "file/" + path.join(__dirname, "../../build");
+  const BOB_SS58 = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
 
   beforeAll(async () => {
     // polyfill text encoder. This is required to test in the jsdom environment
@@ -66,6 +67,27 @@ describe("e2e", () => {
     expect(json_block.block.header.parentHash).toStrictEqual("0x0000000000000000000000000000000000000000000000000000000000000000");
   });
 
+  it("decodes the extrinsics of a block", async () => {
+    const result = await Substrate_Module.chainGetDecodedBlock({
+        url,
+        number: 1
+      },
+      client,
+      uri
+    );
+
+    if (!result.ok) fail(result.error);
+    const block = result.value!;
+    expect(block.number).toStrictEqual(1);
+    // the timestamp inherent is the first, unsigned, extrinsic of every block
+    const timestamp = block.extrinsics[0];
+    expect(timestamp.signed).toBeFalsy();
+    expect(timestamp.pallet).toStrictEqual("Timestamp");
+    expect(timestamp.call).toStrictEqual("set");
+    expect(timestamp.args.now).toBeGreaterThan(0);
+    expect(timestamp.hash).toMatch(/^0x[0-9a-f]{64}$/);
+  });
+
   it("retrieves the chain metadata", async () => {
     const result = await Substrate_Module.chainGetMetadata({
         url
@@ -85,6 +107,54 @@ describe("e2e", () => {
     expect(chainMetadata.errors).toBeTruthy();
   });
 
+  it("lists the pallets and their calls, storage, events and errors", async () => {
+    const pallets = await Substrate_Module.getPallets({ url }, client, uri);
+    if (!pallets.ok) fail(pallets.error);
+    const balances = pallets.value!.find((p) => p.name === "Balances");
+    expect(balances).toBeTruthy();
+
+    const calls = await Substrate_Module.getPalletCalls({ url, pallet: "Balances" }, client, uri);
+    if (!calls.ok) fail(calls.error);
+    const transfer = calls.value!.find((c) => c.name === "transfer");
+    expect(transfer!.fields.map((f) => [f.name, f.typeName])).toStrictEqual([
+      ["dest", "MultiAddress<AccountId32, ()>"],
+      ["value", "Compact<u128>"],
+    ]);
+
+    const storage = await Substrate_Module.getPalletStorage({ url, pallet: "System" }, client, uri);
+    if (!storage.ok) fail(storage.error);
+    const account = storage.value!.find((s) => s.name === "Account");
+    expect(account!.kind).toStrictEqual("Map");
+    expect(account!.hashers).toStrictEqual(["Blake2_128Concat"]);
+    expect(account!.keyTypes).toStrictEqual(["AccountId32"]);
+    expect(account!.modifier).toStrictEqual("Default");
+
+    const events = await Substrate_Module.getPalletEvents({ url, pallet: "Balances" }, client, uri);
+    if (!events.ok) fail(events.error);
+    expect(events.value!.find((e) => e.name === "Transfer")).toBeTruthy();
+
+    const errors = await Substrate_Module.getPalletErrors({ url, pallet: "Balances" }, client, uri);
+    if (!errors.ok) fail(errors.error);
+    expect(errors.value!.find((e) => e.name === "InsufficientBalance")).toBeTruthy();
+  });
+
+  it("diffs the metadata of two blocks", async () => {
+    const result = await Substrate_Module.metadataDiff({
+        url,
+        oldBlockHash: null,
+        oldMetadata: null,
+        newBlockHash: null,
+        newMetadata: null,
+      },
+      client,
+      uri
+    );
+
+    if (!result.ok) fail(result.error);
+    // the test chain is never upgraded
+    expect(result.value).toStrictEqual([]);
+  });
+
   it("state get runtime version", async () => {
     const result = await Substrate_Module.getRuntimeVersion({
         url
@@ -105,6 +175,67 @@ describe("e2e", () => {
     expect(runtimeVersion.state_version).toStrictEqual(1);
   });
 
+  it("get the events of a block", async () => {
+    const result = await Substrate_Module.getEvents({
+        url,
+        number: 1,
+      },
+      client,
+      uri
+    );
+
+    if (!result.ok) fail(result.error);
+    expect(result.value).toBeTruthy();
+    const events = result.value!;
+    // every block applies at least the timestamp inherent
+    const success = events.find(
+      (e) => e.pallet === "System" && e.event === "ExtrinsicSuccess"
+    );
+    expect(success).toBeTruthy();
+    expect(success!.phase).toStrictEqual("ApplyExtrinsic");
+    expect(success!.extrinsicIndex).toStrictEqual(0);
+    expect(success!.fields.dispatch_info).toBeTruthy();
+  });
+
+  it("resolves dispatch errors", async () => {
+    const index = await Substrate_Module.palletCallIndex({
+        url,
+        pallet: "Balances",
+        call: "transfer",
+      },
+      client,
+      uri
+    );
+    if (!index.ok) fail(index.error);
+    const balancesIndex = index.value![0];
+
+    const moduleError = await Substrate_Module.resolveDispatchError({
+        url,
+        dispatchError: { Module: { index: balancesIndex, error: "0x02000000" } },
+      },
+      client,
+      uri
+    );
+    if (!moduleError.ok) fail(moduleError.error);
+    expect(moduleError.value!.kind).toStrictEqual("Module");
+    expect(moduleError.value!.pallet).toStrictEqual("Balances");
+    expect(moduleError.value!.error).toStrictEqual("InsufficientBalance");
+    expect(moduleError.value!.docs.length).toBeGreaterThan(0);
+
+    const badOrigin = await Substrate_Module.resolveDispatchError({
+        url,
+        // SCALE encoded `DispatchError::BadOrigin`
+        dispatchError: "0x02",
+        atNumber: 0,
+      },
+      client,
+      uri
+    );
+    if (!badOrigin.ok) fail(badOrigin.error);
+    expect(badOrigin.value!.kind).toStrictEqual("BadOrigin");
+    expect(badOrigin.value!.pallet).toBeFalsy();
+  });
+
   it("storage value", async () => {
     const result = await Substrate_Module.getStorageValue({
        url,
@@ -140,6 +271,37 @@ describe("e2e", () => {
     ]);
   });
 
+  it("return decoded constant values", async () => {
+    const result = await Substrate_Module.constantDecoded({
+        url,
+        pallet: "Balances",
+        name: "ExistentialDeposit"
+      },
+      client,
+      uri
+    );
+
+    if (!result.ok) fail(result.error);
+    expect(result.value).toStrictEqual("500");
+  });
+
+  it("return all constants of a pallet", async () => {
+    const result = await Substrate_Module.constants({
+        url,
+        pallet: "System",
+      },
+      client,
+      uri
+    );
+
+    if (!result.ok) fail(result.error);
+    expect(result.value).toBeTruthy();
+    const blockWeights = result.value!.find((c) => c.name === "BlockWeights");
+    expect(blockWeights).toBeTruthy();
+    expect(blockWeights!.value.max_block).toBeTruthy();
+    expect(blockWeights!.docs.length).toBeGreaterThan(0);
+  });
+
   it("rpc_methods", async () => {
     const result = await Substrate_Module.rpcMethods({
         url,
@@ -172,6 +334,22 @@ describe("e2e", () => {
     expect(result.value).toBeTruthy();
   });
 
+  it("get storage maps keyed by account id", async () => {
+    const result = await Substrate_Module.getStorageMapDecoded({
+        url,
+        pallet: "System",
+        storage: "Account",
+        key: address,
+      },
+      client,
+      uri
+    );
+
+    if (!result.ok) fail(result.error);
+    expect(result.value).toBeTruthy();
+    expect(BigInt(result.value.data.free)).toBeGreaterThan(BigInt(0));
+  });
+
   it("get storage maps paged", async () => {
     const result = await Substrate_Module.getStorageMapPaged({
         url,
@@ -189,6 +367,375 @@ describe("e2e", () => {
     expect(result.value).toBeTruthy();
   });
 
+  it("get decoded storage values", async () => {
+    const result = await Substrate_Module.getStorageValueDecoded({
+        url,
+        pallet: "Balances",
+        storage: "TotalIssuance",
+      },
+      client,
+      uri
+    );
+
+    if (!result.ok) fail(result.error);
+    expect(typeof result.value).toStrictEqual("string");
+    expect(BigInt(result.value)).toBeGreaterThan(BigInt(0));
+  });
+
+  it("read storage, runtime version and metadata at a block", async () => {
+    const genesis = await Substrate_Module.blockHash({ url, number: 0 }, client, uri);
+    if (!genesis.ok) fail(genesis.error);
+
+    const byNumber = await Substrate_Module.getStorageValueDecoded({
+        url,
+        pallet: "System",
+        storage: "Number",
+        atNumber: 0,
+      },
+      client,
+      uri
+    );
+    if (!byNumber.ok) fail(byNumber.error);
+    expect(byNumber.value).toStrictEqual(0);
+
+    const byHash = await Substrate_Module.getStorageValueDecoded({
+        url,
+        pallet: "System",
+        storage: "Number",
+        at: genesis.value,
+        atNumber: 1,
+      },
+      client,
+      uri
+    );
+    if (!byHash.ok) fail(byHash.error);
+    expect(byHash.value).toStrictEqual(0);
+
+    const version = await Substrate_Module.getRuntimeVersion({ url, at: genesis.value }, client, uri);
+    if (!version.ok) fail(version.error);
+    expect(version.value!.spec_version).toBeGreaterThan(0);
+
+    const metadata = await Substrate_Module.chainGetMetadata({ url, atNumber: 0 }, client, uri);
+    if (!metadata.ok) fail(metadata.error);
+    expect(metadata.value).toBeTruthy();
+  });
+
+  it("get decoded storage maps", async () => {
+    const result = await Substrate_Module.getStorageMapDecoded({
+        url,
+        pallet: "System",
+        storage: "BlockHash",
+        key: 0,
+      },
+      client,
+      uri
+    );
+
+    if (!result.ok) fail(result.error);
+    expect(result.value).toMatch(/^0x[0-9a-f]{64}$/);
+  });
+
+  it("get decoded storage maps paged", async () => {
+    const result = await Substrate_Module.getStorageMapPagedDecoded({
+        url,
+        pallet: "System",
+        storage: "BlockHash",
+        count: 10,
+        nextTo: null,
+      },
+      client,
+      uri
+    );
+
+    if (!result.ok) fail(result.error);
+    expect(result.value).toBeTruthy();
+    expect(result.value!.length).toBeGreaterThan(0);
+  });
+
+  it("get storage n maps with the hasher of each key", async () => {
+    const map = await Substrate_Module.getStorageMapDecoded({
+        url,
+        pallet: "System",
+        storage: "BlockHash",
+        key: 0,
+      },
+      client,
+      uri
+    );
+    const nMap = await Substrate_Module.getStorageNMapDecoded({
+        url,
+        pallet: "System",
+        storage: "BlockHash",
+        keys: [0],
+      },
+      client,
+      uri
+    );
+
+    if (!map.ok) fail(map.error);
+    if (!nMap.ok) fail(nMap.error);
+    expect(nMap.value).toStrictEqual(map.value);
+
+    const tooMany = await Substrate_Module.getStorageNMap({
+        url,
+        pallet: "System",
+        storage: "BlockHash",
+        keys: [0, 1],
+      },
+      client,
+      uri
+    );
+    if (!tooMany.ok) fail(tooMany.error);
+    expect(tooMany.value).toBeNull();
+  });
+
+  it("get storage n maps paged by key prefix", async () => {
+    const all = await Substrate_Module.getStorageNMapPagedDecoded({
+        url,
+        pallet: "System",
+        storage: "BlockHash",
+        keys: [],
+        count: 10,
+        nextTo: null,
+      },
+      client,
+      uri
+    );
+    if (!all.ok) fail(all.error);
+    expect(all.value!.length).toBeGreaterThan(0);
+
+    const genesis = await Substrate_Module.getStorageNMapPagedDecoded({
+        url,
+        pallet: "System",
+        storage: "BlockHash",
+        keys: [0],
+        count: 10,
+        nextTo: null,
+      },
+      client,
+      uri
+    );
+    if (!genesis.ok) fail(genesis.error);
+    expect(genesis.value!.length).toStrictEqual(1);
+  });
+
+  it("iterate storage map entries with their keys", async () => {
+    const first = await Substrate_Module.getStorageEntries({
+        url,
+        pallet: "System",
+        storage: "Account",
+        count: 1,
+      },
+      client,
+      uri
+    );
+    if (!first.ok) fail(first.error);
+    expect(first.value!.entries.length).toStrictEqual(1);
+    expect(first.value!.cursor).toBeTruthy();
+
+    const entry = first.value!.entries[0];
+    expect(entry.keyRecoverable).toBeTruthy();
+    expect(entry.rawKey).toMatch(/^0x[0-9a-f]+$/);
+    expect(typeof entry.key).toStrictEqual("string");
+    expect(entry.value.data).toBeTruthy();
+
+    const next = await Substrate_Module.getStorageEntries({
+        url,
+        pallet: "System",
+        storage: "Account",
+        count: 1,
+        cursor: first.value!.cursor,
+      },
+      client,
+      uri
+    );
+    if (!next.ok) fail(next.error);
+    expect(next.value!.entries.length).toStrictEqual(1);
+    expect(next.value!.entries[0].key).not.toStrictEqual(entry.key);
+  });
+
+  it("get storage verified with a read proof", async () => {
+    const block = await Substrate_Module.blockHash({ url, number: 1 }, client, uri);
+    if (!block.ok) fail(block.error);
+
+    const result = await Substrate_Module.getStorageVerified({
+        url,
+        items: [
+          { pallet: "System", storage: "Number" },
+          { pallet: "System", storage: "Account", keys: [address] },
+        ],
+        at: block.value,
+      },
+      client,
+      uri
+    );
+
+    if (!result.ok) fail(result.error);
+    expect(result.value!.block).toStrictEqual(block.value);
+    expect(result.value!.stateRoot).toMatch(/^0x[0-9a-f]{64}$/);
+    expect(result.value!.values[0]).toStrictEqual(1);
+    expect(BigInt(result.value!.values[1].data.free)).toBeGreaterThan(BigInt(0));
+  });
+
+  it("read child tries", async () => {
+    const keys = await Substrate_Module.getChildStorageKeysPaged({
+        url,
+        child: { crowdloanFundIndex: 2000 },
+        count: 10,
+      },
+      client,
+      uri
+    );
+    if (!keys.ok) fail(keys.error);
+    expect(keys.value).toStrictEqual([]);
+
+    const value = await Substrate_Module.getChildStorage({
+        url,
+        child: { trieId: "0x0102" },
+        key: "0x00",
+      },
+      client,
+      uri
+    );
+    if (!value.ok) fail(value.error);
+    expect(value.value).toBeNull();
+  });
+
+  it("get the history of a storage value", async () => {
+    const genesis = await Substrate_Module.blockHash({ url, number: 0 }, client, uri);
+    const second = await Substrate_Module.blockHash({ url, number: 2 }, client, uri);
+    if (!genesis.ok) fail(genesis.error);
+    if (!second.ok) fail(second.error);
+
+    const result = await Substrate_Module.storageHistory({
+        url,
+        pallet: "System",
+        storage: "Number",
+        fromBlock: 0,
+        toBlock: 2,
+      },
+      client,
+      uri
+    );
+
+    if (!result.ok) fail(result.error);
+    const changes = result.value!;
+    expect(changes[0].block).toStrictEqual(genesis.value);
+    expect(changes[changes.length - 1].block).toStrictEqual(second.value);
+    expect(changes[changes.length - 1].value).toStrictEqual(2);
+    expect(changes[changes.length - 1].rawValue).toStrictEqual("0x02000000");
+  });
+
+  it("get the default value of absent storage", async () => {
+    const fresh = "5C62Ck4UrFPiBtoCmeSrgF7x9yv9mn38446dhCpsi2mLHiFT";
+    const absent = await Substrate_Module.getStorage({
+        url,
+        pallet: "System",
+        storage: "Account",
+        keys: [fresh],
+      },
+      client,
+      uri
+    );
+    if (!absent.ok) fail(absent.error);
+    expect(absent.value!.stored).toBeFalsy();
+    expect(absent.value!.value.nonce).toStrictEqual(0);
+    expect(absent.value!.value.data.free).toStrictEqual("0");
+
+    const stored = await Substrate_Module.getStorage({
+        url,
+        pallet: "System",
+        storage: "Account",
+        keys: [address],
+      },
+      client,
+      uri
+    );
+    if (!stored.ok) fail(stored.error);
+    expect(stored.value!.stored).toBeTruthy();
+
+    const info = await Substrate_Module.accountInfo({ url, account: fresh }, client, uri);
+    if (!info.ok) fail(info.error);
+    expect(info.value!.nonce).toStrictEqual(0);
+  });
+
+  it("get storage of several pallets in one request", async () => {
+    const result = await Substrate_Module.getStorageMultiDecoded({
+        url,
+        items: [
+          { pallet: "Balances", storage: "TotalIssuance" },
+          { pallet: "System", storage: "BlockHash", keys: [0] },
+          { pallet: "System", storage: "Account", keys: [address] },
+          { pallet: "System", storage: "BlockHash", keys: [4294967295] },
+        ],
+      },
+      client,
+      uri
+    );
+
+    if (!result.ok) fail(result.error);
+    const [issuance, genesis, account, unset] = result.value!;
+    expect(BigInt(issuance)).toBeGreaterThan(BigInt(0));
+    expect(genesis).toMatch(/^0x[0-9a-f]{64}$/);
+    expect(BigInt(account.data.free)).toBeGreaterThan(BigInt(0));
+    // BlockHash has a default value
+    expect(unset).toStrictEqual("0x" + "00".repeat(32));
+  });
+
+  it("compute and identify storage keys from the metadata", async () => {
+    const api = await ApiPromise.create({ throwOnConnect: true });
+    const metadata = api.runtimeMetadata.toHex();
+
+    const account = await Substrate_Module.storageKey({
+        url,
+        metadata,
+        pallet: "System",
+        storage: "Account",
+        keys: [address],
+      },
+      client,
+      uri
+    );
+    if (!account.ok) fail(account.error);
+    expect(account.value).toStrictEqual(api.query.system.account.key(address));
+
+    const number = await Substrate_Module.storageKey({
+        url,
+        pallet: "System",
+        storage: "Number",
+      },
+      client,
+      uri
+    );
+    if (!number.ok) fail(number.error);
+    expect(number.value).toStrictEqual(api.query.system.number.key());
+
+    const identified = await Substrate_Module.identifyStorageKey({
+        url,
+        metadata,
+        key: account.value!,
+      },
+      client,
+      uri
+    );
+    if (!identified.ok) fail(identified.error);
+    expect(identified.value!.pallet).toStrictEqual("System");
+    expect(identified.value!.storage).toStrictEqual("Account");
+    expect(identified.value!.keys.length).toStrictEqual(1);
+
+    const unknown = await Substrate_Module.identifyStorageKey({
+        url,
+        key: "0x" + "00".repeat(32),
+      },
+      client,
+      uri
+    );
+    expect(unknown.ok).toBeTruthy();
+    expect(unknown.value).toBeFalsy();
+
+    await api.disconnect();
+  });
+
   it("get account info of Alice from chain", async () => {
     const result = await Substrate_Module.accountInfo({
         url,
@@ -235,7 +782,6 @@ describe("e2e", () => {
       throwOnConnect: true
     });
 
-    const BOB_SS58 = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
     const bobBalanceBefore = await balanceOf(api, BOB_SS58);
     const balancesTransfer = api.registry.createType("BalancesTransfer",  { dest: BOB_SS58, value: 1000000 } );
     const ex = await Substrate_Module.sign(
@@ -244,7 +790,7 @@ describe("e2e", () => {
         signer: address,
         pallet_name: "Balances",
         call_name: "transfer",
-        call_params: balancesTransfer.toHex(),
+        call_params: JSON.stringify(balancesTransfer.toHex()),
       },
       client,
       uri
@@ -262,6 +808,96 @@ describe("e2e", () => {
     await api.disconnect();
   });
 
+  it("Can sign mortal and immortal extrinsics", () => withApi(async (api) => {
+    const sign = (mortality?: number) => Substrate_Module.sign(
+      transferArgs({ mortality }),
+      client,
+      uri
+    );
+
+    const mortal = api.createType("Extrinsic", checkInvokeResult(await sign(32)));
+    expect(mortal.era.isMortalEra).toBeTruthy();
+    expect(mortal.era.asMortalEra.period.toNumber()).toStrictEqual(32);
+
+    const byDefault = api.createType("Extrinsic", checkInvokeResult(await sign()));
+    expect(byDefault.era.asMortalEra.period.toNumber()).toStrictEqual(64);
+
+    const immortal = api.createType("Extrinsic", checkInvokeResult(await sign(0)));
+    expect(immortal.era.isImmortalEra).toBeTruthy();
+  }));
+
+  it("Can sign with a tip, and only pay fees in an asset on asset chains", () => withApi(async (api) => {
+    const tipped = await Substrate_Module.sign(transferArgs({ tip: "1000" }), client, uri);
+    const xt = api.createType("Extrinsic", checkInvokeResult(tipped));
+    expect(xt.tip.toString()).toStrictEqual("1000");
+
+    // the test chain has no ChargeAssetTxPayment extension
+    const inAsset = await Substrate_Module.sign(
+      transferArgs({ tip: "1000", feeAssetId: JSON.stringify(1984) }),
+      client,
+      uri
+    );
+    expect(inAsset.ok).toBeTruthy();
+    expect(inAsset.value).toBeFalsy();
+  }));
+
+  it("Can sign the payload fields or the raw payload bytes", () => withApi(async (api) => {
+    for (const signRaw of [false, true]) {
+      const bobBalanceBefore = await balanceOf(api, BOB_SS58);
+      const signed = checkInvokeResult(
+        await Substrate_Module.sign(transferArgs({ signRaw }), client, uri)
+      );
+      const xt = api.createType("Extrinsic", signed);
+      expect(xt.signer.toString()).toStrictEqual(address);
+
+      // the chain only includes the transfer if the signature is valid
+      const result = await Substrate_Module.submit({ url, signedExtrinsic: String(signed) }, client, uri);
+      checkInvokeResult(result);
+
+      // Wait for finalized.
+      await new Promise((r) => setTimeout(r, 10000));
+      const bobBalanceAfter = await balanceOf(api, BOB_SS58);
+      expect(bobBalanceAfter).toBeGreaterThan(bobBalanceBefore);
+    }
+  }));
+
+  it("Can sign a call given its arguments as json", () => withApi(async (api) => {
+    const bobBalanceBefore = await balanceOf(api, BOB_SS58);
+    const ex = await Substrate_Module.sign_and_submit(transferArgs(), client, uri);
+    checkInvokeResult(ex);
+
+    // Wait for finalized.
+    await new Promise((r) => setTimeout(r, 10000));
+    const bobBalanceAfter = await balanceOf(api, BOB_SS58);
+    expect(bobBalanceAfter).toBeGreaterThan(bobBalanceBefore);
+  }));
+
+  // the args of `sign` for a transfer from Alice to Bob, with the signing `options`
+  function transferArgs(options: {
+    mortality?: number,
+    tip?: string,
+    feeAssetId?: string,
+    signRaw?: boolean,
+  } = {}) {
+    return {
+      url,
+      signer: address,
+      pallet_name: "Balances",
+      call_name: "transfer",
+      call_params: JSON.stringify({ dest: BOB_SS58, value: "1000000" }),
+      ...options,
+    };
+  }
+
+  async function withApi(test: (api: ApiPromise) => Promise<void>) {
+    const api = await ApiPromise.create({ throwOnConnect: true });
+    try {
+      await test(api);
+    } finally {
+      await api.disconnect();
+    }
+  }
+
   async function balanceOf(api: ApiPromise, address: string) {
     const info = await api.query.system.account(address);
     return Number((info as any).toJSON().data.free);
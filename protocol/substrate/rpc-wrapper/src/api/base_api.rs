@@ -14,6 +14,7 @@ use crate::{
         HttpResponseType,
     },
 };
+use codec::Encode;
 use frame_metadata::RuntimeMetadataPrefixed;
 use polywrap_wasm_rs::Map;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -36,6 +37,9 @@ pub struct JsonResult {
     result: Option<serde_json::Value>,
 }
 
+/// The highest metadata version this wrapper can read
+const LATEST_METADATA_VERSION: u32 = 15;
+
 pub struct BaseApi {
     /// the url of the substrate node we are running the rpc call from
     url: String,
@@ -48,11 +52,22 @@ impl BaseApi {
         }
     }
 
-    /// Get the runtime metadata of a substrate node.
-    pub fn fetch_runtime_metadata(
+    /// Get the runtime metadata of a substrate node as of block `at`, or the best block
+    /// if `None`.
+    ///
+    /// The highest supported version is requested first through the
+    /// `Metadata_metadata_at_version` runtime api, falling back to `state_getMetadata`
+    /// on runtimes which don't expose it.
+    pub fn fetch_runtime_metadata_at(
         &self,
+        at: Option<H256>,
     ) -> Result<Option<RuntimeMetadataPrefixed>, Error> {
-        let value = self.json_request_value("state_getMetadata", ())?;
+        if let Some(rt_metadata) =
+            self.fetch_runtime_metadata_at_version(LATEST_METADATA_VERSION, at)?
+        {
+            return Ok(Some(rt_metadata));
+        }
+        let value = self.json_request_value("state_getMetadata", [at])?;
         match value {
             Some(value) => {
                 let value_str = value
@@ -67,9 +82,49 @@ impl BaseApi {
         }
     }
 
-    /// Get the metadata of the substrate chain
-    pub fn fetch_metadata(&self) -> Result<Option<Metadata>, Error> {
-        let rt_metadata = self.fetch_runtime_metadata()?;
+    /// Get the runtime metadata at a specific `version` using the `Metadata_metadata_at_version`
+    /// runtime api.
+    ///
+    /// Returns `None` if the runtime doesn't have the api or doesn't support that version.
+    pub fn fetch_runtime_metadata_at_version(
+        &self,
+        version: u32,
+        at: Option<H256>,
+    ) -> Result<Option<RuntimeMetadataPrefixed>, Error> {
+        let value = self.json_request_value(
+            "state_call",
+            (
+                "Metadata_metadata_at_version",
+                format!("0x{}", hex::encode(version.encode())),
+                at,
+            ),
+        )?;
+        match value {
+            Some(value) => {
+                let value_str = value
+                    .as_str()
+                    .expect("Expecting a string value on the result");
+                let data = Vec::from_hex(value_str)?;
+                // the runtime api returns an `Option<OpaqueMetadata>`
+                let opaque: Option<Vec<u8>> =
+                    Decode::decode(&mut data.as_slice())?;
+                match opaque {
+                    Some(bytes) => Ok(Some(RuntimeMetadataPrefixed::decode(
+                        &mut bytes.as_slice(),
+                    )?)),
+                    None => Ok(None),
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Get the metadata of the substrate chain as of block `at`, or the best block if `None`.
+    pub fn fetch_metadata_at(
+        &self,
+        at: Option<H256>,
+    ) -> Result<Option<Metadata>, Error> {
+        let rt_metadata = self.fetch_runtime_metadata_at(at)?;
         match rt_metadata {
             Some(rt_metadata) => {
                 let metadata = Metadata::try_from(rt_metadata)?;
@@ -109,6 +164,23 @@ impl BaseApi {
         }
     }
 
+    /// return the hash of the block given either its `hash` as hex, which takes precedence,
+    /// or its `number`. `None` stands for the best block when neither is given.
+    pub fn resolve_block_hash(
+        &self,
+        hash: Option<&str>,
+        number: Option<u32>,
+    ) -> Result<Option<H256>, Error> {
+        match (hash, number) {
+            (Some(hash), _) => Ok(Some(H256::from_hex(hash)?)),
+            (None, Some(number)) => match self.fetch_block_hash(number)? {
+                Some(hash) => Ok(Some(hash)),
+                None => Err(Error::BlockNotFound(number)),
+            },
+            (None, None) => Ok(None),
+        }
+    }
+
     /// return the genesis hash of the block chain
     pub fn fetch_genesis_hash(&self) -> Result<Option<H256>, Error> {
         self.fetch_block_hash(0)
@@ -157,10 +229,13 @@ impl BaseApi {
         Ok(value)
     }
 
-    pub fn fetch_runtime_version(
+    /// return the runtime version as of block `at`, or the best block if `None`.
+    pub fn fetch_runtime_version_at(
         &self,
+        at: Option<H256>,
     ) -> Result<Option<RuntimeVersion>, Error> {
-        let version = self.json_request_value("state_getRuntimeVersion", ())?;
+        let version =
+            self.json_request_value("state_getRuntimeVersion", [at])?;
         match version {
             Some(version) => {
                 let rt_version: RuntimeVersion =
@@ -0,0 +1,26 @@
+//!
+//! Block API
+//!
+//! Extension to the API for retrieving blocks with decoded extrinsics
+//!
+
+use crate::{api::Api, types::block::DecodedBlock, Error};
+use sp_core::H256;
+
+impl Api {
+    /// Retrieve the block `hash` and decode its extrinsics.
+    ///
+    /// The extrinsics are decoded with the current metadata, so blocks authored by an
+    /// older runtime may fail to decode.
+    pub fn fetch_decoded_block(
+        &self,
+        hash: H256,
+    ) -> Result<Option<DecodedBlock>, Error> {
+        match self.base_api.fetch_opaque_block_by_hash(hash)? {
+            Some(block) => {
+                Ok(Some(DecodedBlock::from_json(&self.metadata, hash, &block)?))
+            }
+            None => Ok(None),
+        }
+    }
+}
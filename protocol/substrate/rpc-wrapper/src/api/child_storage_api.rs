@@ -0,0 +1,92 @@
+//!
+//! Child Storage API
+//!
+//! Extension to the API for retrieving data from the child tries of the storage
+//!
+
+use crate::{
+    api::{storage_api::parse_storage_keys, Api},
+    utils::FromHexStr,
+    Error,
+};
+use sp_core::{
+    storage::{PrefixedStorageKey, StorageKey},
+    H256,
+};
+
+impl Api {
+    /// Retrieve the value in bytes at `key` in the child trie `child_storage_key`.
+    pub fn fetch_opaque_child_storage(
+        &self,
+        child_storage_key: &PrefixedStorageKey,
+        key: &StorageKey,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let value = self.base_api.json_request_value(
+            "childstate_getStorage",
+            (child_storage_key, key, self.at),
+        )?;
+        match value {
+            Some(serde_json::Value::String(hex)) => {
+                Ok(Some(Vec::from_hex(&hex)?))
+            }
+            Some(value) => Err(Error::ResponseJsonError(value)),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the hash of the value at `key` in the child trie `child_storage_key`.
+    pub fn fetch_child_storage_hash(
+        &self,
+        child_storage_key: &PrefixedStorageKey,
+        key: &StorageKey,
+    ) -> Result<Option<H256>, Error> {
+        let value = self.base_api.json_request_value(
+            "childstate_getStorageHash",
+            (child_storage_key, key, self.at),
+        )?;
+        match value {
+            Some(serde_json::Value::String(hex)) => {
+                Ok(Some(H256::from_hex(&hex)?))
+            }
+            Some(value) => Err(Error::ResponseJsonError(value)),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the size in bytes of the value at `key` in the child trie `child_storage_key`.
+    pub fn fetch_child_storage_size(
+        &self,
+        child_storage_key: &PrefixedStorageKey,
+        key: &StorageKey,
+    ) -> Result<Option<u64>, Error> {
+        let value = self.base_api.json_request_value(
+            "childstate_getStorageSize",
+            (child_storage_key, key, self.at),
+        )?;
+        match value {
+            Some(value) => match value.as_u64() {
+                Some(size) => Ok(Some(size)),
+                None => Err(Error::ResponseJsonError(value)),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve a paged list of the keys of the child trie `child_storage_key`.
+    /// `prefix` - the prefix of the keys to return, all the keys if `None`.
+    /// `count` - the number of keys to be returned
+    /// `start_key` - the key used as an offset marker to start getting the list of keys.
+    pub fn fetch_child_storage_keys_paged(
+        &self,
+        child_storage_key: &PrefixedStorageKey,
+        prefix: Option<StorageKey>,
+        count: u32,
+        start_key: Option<StorageKey>,
+    ) -> Result<Option<Vec<StorageKey>>, Error> {
+        let value = self.base_api.json_request_value(
+            "childstate_getKeysPaged",
+            (child_storage_key, prefix, count, start_key, self.at),
+        )?;
+        Ok(value.map(parse_storage_keys))
+    }
+}
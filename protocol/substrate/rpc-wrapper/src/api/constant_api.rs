@@ -4,10 +4,20 @@
 //! Extension to the API for retrieving chain constant data
 //!
 
-use crate::{api::Api, Error};
-use frame_metadata::PalletConstantMetadata;
+use crate::{
+    api::Api,
+    types::{decode, metadata::MetadataError},
+    Error,
+};
+use frame_metadata::v14::PalletConstantMetadata;
 use scale_info::{form::PortableForm, Type};
 
+/// A constant with its value in json, `None` when it doesn't decode.
+pub type DecodedConstant<'a> = (
+    &'a PalletConstantMetadata<PortableForm>,
+    Option<serde_json::Value>,
+);
+
 impl Api {
     pub fn constant_metadata(
         &self,
@@ -33,4 +43,112 @@ impl Api {
     ) -> Result<Vec<u8>, Error> {
         Ok(self.constant_metadata(module, constant_name)?.value.clone())
     }
+
+    /// Return the constants of pallet `module`, in the order they are declared.
+    pub fn pallet_constants(
+        &self,
+        module: &str,
+    ) -> Result<&[PalletConstantMetadata<PortableForm>], Error> {
+        self.metadata
+            .metadata
+            .pallets
+            .iter()
+            .find(|pallet| pallet.name == module)
+            .map(|pallet| pallet.constants.as_slice())
+            .ok_or_else(|| MetadataError::PalletNotFound(module.to_string()))
+            .map_err(Into::into)
+    }
+
+    /// Decode the value of `constant` into json using its type in the metadata.
+    pub fn decode_constant(
+        &self,
+        constant: &PalletConstantMetadata<PortableForm>,
+    ) -> Result<serde_json::Value, Error> {
+        let registry = self.metadata.type_registry()?;
+        Ok(decode::decode_value(
+            registry,
+            constant.ty.id,
+            &constant.value,
+        )?)
+    }
+
+    /// Decode the constants of pallet `module`, in the order they are declared, each with
+    /// its value in json or `None` when it doesn't decode with its type in the metadata.
+    pub fn decode_pallet_constants(
+        &self,
+        module: &str,
+    ) -> Result<Vec<DecodedConstant<'_>>, Error> {
+        Ok(self
+            .pallet_constants(module)?
+            .iter()
+            .map(|constant| (constant, self.decode_constant(constant).ok()))
+            .collect())
+    }
+
+    pub fn fetch_constant_value_json(
+        &self,
+        module: &str,
+        constant_name: &str,
+    ) -> Result<serde_json::Value, Error> {
+        self.decode_constant(self.constant_metadata(module, constant_name)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api::BaseApi, types::test_metadata};
+    use codec::Encode;
+    use frame_metadata::v14::PalletMetadata;
+    use scale_info::meta_type;
+    use serde_json::json;
+    use sp_core::H256;
+    use sp_version::RuntimeVersion;
+
+    #[test]
+    fn test_decode_pallet_constants() {
+        let constant = |name, value| PalletConstantMetadata {
+            name,
+            ty: meta_type::<u32>(),
+            value,
+            docs: vec![],
+        };
+        let pallet = PalletMetadata {
+            name: "Staking",
+            storage: None,
+            calls: None,
+            event: None,
+            constants: vec![
+                constant("SessionsPerEra", 6u32.encode()),
+                // truncated, as by a runtime with a wrong type in its metadata
+                constant("BondingDuration", vec![28]),
+                constant("MaxNominations", 16u32.encode()),
+            ],
+            error: None,
+            index: 7,
+        };
+        let api = Api {
+            base_api: BaseApi::new("http://0.0.0.0:9933"),
+            metadata: test_metadata::metadata_with_pallets(vec![pallet]),
+            genesis_hash: H256::repeat_byte(1),
+            runtime_version: RuntimeVersion::default(),
+            at: None,
+        };
+
+        let constants: Vec<_> = api
+            .decode_pallet_constants("Staking")
+            .unwrap()
+            .into_iter()
+            .map(|(constant, value)| (constant.name.as_str(), value))
+            .collect();
+        assert_eq!(
+            constants,
+            vec![
+                ("SessionsPerEra", Some(json!(6))),
+                ("BondingDuration", None),
+                ("MaxNominations", Some(json!(16))),
+            ]
+        );
+        assert!(api.decode_pallet_constants("Sudo").is_err());
+    }
 }
@@ -0,0 +1,26 @@
+//!
+//! Events API
+//!
+//! Extension to the API for retrieving the events emitted in a block
+//!
+
+use crate::{
+    api::Api,
+    types::events::{self, EventRecord},
+    Error,
+};
+
+impl Api {
+    /// Retrieve the events emitted in block `self.at`, or in the best block if `None`.
+    ///
+    /// The events are decoded with the metadata the api was created with, so it must be
+    /// the metadata of the runtime of the block, see [`Api::new_at`].
+    pub fn fetch_events(&self) -> Result<Vec<EventRecord>, Error> {
+        let storage_key =
+            self.metadata.storage_value_key("System", "Events")?;
+        match self.fetch_opaque_storage_by_key_hash(storage_key)? {
+            Some(bytes) => events::decode_event_records(&self.metadata, &bytes),
+            None => Ok(vec![]),
+        }
+    }
+}
@@ -5,17 +5,49 @@
 //!
 
 use crate::{
-    api::Api,
+    api::{storage_api, Api},
     error::Error,
     types::{
-        account_info::AccountInfo, extrinsic::ExtrinsicBuilder,
-        extrinsic_params::ExtrinsicParams,
+        account_info::AccountInfo,
+        encode,
+        extrinsic::{ExtrinsicBuilder, SignatureScheme},
+        extrinsic_params::{ExtrinsicParams, PlainTip},
+        metadata::{Metadata, MetadataError},
     },
-    utils::Encoded,
+    utils::{Encoded, FromHexStr},
 };
-use codec::Encode;
-use sp_core::crypto::{Pair, Ss58Codec};
-use sp_runtime::{traits::IdentifyAccount, AccountId32, MultiSigner};
+use codec::{Decode, Encode};
+use scale_info::{Type, TypeDef};
+use sp_core::{
+    crypto::{Pair, Ss58Codec},
+    H256,
+};
+use sp_runtime::{
+    generic::{Era, Header},
+    traits::{BlakeTwo256, IdentifyAccount},
+    AccountId32, MultiSigner,
+};
+
+/// The number of blocks signed extrinsics stay valid for when no mortality is given, as
+/// in polkadot.js. Other periods are rounded up to a power of two by [`Era::mortal`].
+pub const DEFAULT_MORTALITY: u64 = 64;
+
+/// Options of the signed extensions of an extrinsic, left to their default when `None`.
+#[derive(Clone, Debug, Default)]
+pub struct SignOptions {
+    /// The number of blocks the extrinsic is valid for from the finalized head,
+    /// [`DEFAULT_MORTALITY`] if `None`. A mortality of `0` makes it immortal.
+    pub mortality: Option<u64>,
+    /// Tip for the block author, in the smallest unit of the asset paying the fees.
+    pub tip: Option<u128>,
+    /// The json asset id to pay the fees in with `ChargeAssetTxPayment`, encoded with the
+    /// asset id type of the chain such as a `u32` or a `MultiLocation`.
+    pub fee_asset_id: Option<serde_json::Value>,
+    /// Sign the encoded payload with `signRaw`, for signers which can't `signPayload`.
+    /// Extrinsics paying the fees in an asset are always signed with `signRaw`, as the
+    /// payload of `signPayload` has no field for the asset id.
+    pub sign_raw: bool,
+}
 
 impl Api {
     pub fn signer_account<P>(signer: &P) -> AccountId32
@@ -38,6 +70,21 @@ impl Api {
         }
     }
 
+    /// The nonce of the account with `address`, an SS58 address or the hex of the account
+    /// on chains with 20 bytes accounts.
+    pub fn get_nonce_for_address(&self, address: &str) -> Result<u32, Error> {
+        if !self.metadata.has_type_registry() {
+            let account_id = AccountId32::from_ss58check(address)?;
+            return self.get_nonce_for_account(&account_id);
+        }
+        let keys = account_keys(&self.metadata, address)?;
+        // the nonce leads the `AccountInfo` of every chain
+        match self.fetch_opaque_storage_n_map("System", "Account", &keys)? {
+            Some(bytes) => Ok(u32::decode(&mut bytes.as_slice())?),
+            None => Ok(0),
+        }
+    }
+
     pub fn get_nonce<P>(&self, signer: &P) -> Result<u32, Error>
     where
         P: Pair,
@@ -55,64 +102,229 @@ impl Api {
     }
 
     // SCALE encode call data to bytes (pallet u8, call u8, call params).
+    //
+    // `call_params` is either a hex string of the already encoded params, or the json
+    // arguments of the call which are encoded using the metadata type registry.
     fn encode_call_data(
         &self,
         pallet_name: &str,
         call_name: &str,
-        call_params: &str,
+        call_params: &serde_json::Value,
     ) -> Result<Encoded, Error> {
         let mut out = vec![];
-        let [pallet_index, call_name] =
+        let [pallet_index, call_index] =
             self.metadata.pallet_call_index(pallet_name, call_name)?;
-        let call_params = hex::decode(call_params.trim_start_matches("0x"))
-            .expect("Failed to decode call data");
 
         pallet_index.encode_to(&mut out);
-        call_name.encode_to(&mut out);
-        Encoded(call_params).encode_to(&mut out);
+        call_index.encode_to(&mut out);
+        match call_params {
+            serde_json::Value::String(hex_params) => {
+                Encoded(Vec::from_hex(hex_params)?).encode_to(&mut out);
+            }
+            args => {
+                let call =
+                    self.metadata.call_variant(pallet_name, call_name)?;
+                encode::encode_fields(
+                    self.metadata.type_registry()?,
+                    &call.fields,
+                    args,
+                    &mut out,
+                )?;
+            }
+        }
 
         Ok(Encoded(out))
     }
 
-    // Construct custom additional/extra params.
+    /// The hash and number of the finalized head, which mortal eras start from.
+    ///
+    /// The finalized head can't be reverted, so the extrinsic is never valid on a fork
+    /// other than the one it was signed for.
+    pub fn fetch_mortality_checkpoint(&self) -> Result<(H256, u32), Error> {
+        let hash = self
+            .chain_get_finalized_head()?
+            .ok_or(Error::NoFinalizedHead)?;
+        let header: Header<u32, BlakeTwo256> =
+            self.chain_get_header(hash)?.ok_or(Error::NoFinalizedHead)?;
+        Ok((hash, header.number))
+    }
+
+    // Construct custom additional/extra params, with the signed extensions of the chain.
     fn construct_params(
         &self,
-        account_id: &AccountId32,
+        signer: &str,
+        options: &SignOptions,
     ) -> Result<ExtrinsicParams, Error> {
-        Ok(ExtrinsicParams::new(
-            self.get_nonce_for_account(&account_id)?,
+        let mortality = options.mortality.unwrap_or(DEFAULT_MORTALITY);
+        let (era, checkpoint, checkpoint_number) = match mortality {
+            0 => (Era::Immortal, self.genesis_hash, 0),
+            period => {
+                let (hash, number) = self.fetch_mortality_checkpoint()?;
+                (Era::mortal(period, number.into()), hash, number)
+            }
+        };
+        let signed_extensions =
+            &self.metadata.metadata.extrinsic.signed_extensions;
+        let mut params = ExtrinsicParams::new(
+            self.get_nonce_for_address(signer)?,
             self.runtime_version.spec_version,
             self.runtime_version.transaction_version,
             self.genesis_hash,
-            None,
-            None,
-            None,
-        ))
+            Some(era),
+            Some(checkpoint),
+            options.tip.map(PlainTip::new),
+        )
+        .with_checkpoint_number(checkpoint_number);
+        if let Some(fee_asset_id) = &options.fee_asset_id {
+            params = params
+                .with_fee_asset_id(self.encode_fee_asset_id(fee_asset_id)?);
+        }
+        params.with_signed_extensions(
+            signed_extensions
+                .iter()
+                .map(|extension| extension.identifier.as_str()),
+        )
     }
 
-    /// Create signed extrinsic.
+    // SCALE encode the json `fee_asset_id` with the asset id type of the chain, found in
+    // the `Option` of the `asset_id` field of the `ChargeAssetTxPayment` extension.
+    fn encode_fee_asset_id(
+        &self,
+        fee_asset_id: &serde_json::Value,
+    ) -> Result<Encoded, Error> {
+        let not_found =
+            || Error::SignedExtensionNotFound("ChargeAssetTxPayment".into());
+        let extension = self
+            .metadata
+            .metadata
+            .extrinsic
+            .signed_extensions
+            .iter()
+            .find(|extension| extension.identifier == "ChargeAssetTxPayment")
+            .ok_or_else(not_found)?;
+        let registry = self.metadata.type_registry()?;
+        let asset_id_option = match registry.resolve(extension.ty.id) {
+            Some(Type {
+                type_def: TypeDef::Composite(composite),
+                ..
+            }) => composite
+                .fields
+                .iter()
+                .find(|field| field.name.as_deref() == Some("asset_id")),
+            _ => None,
+        }
+        .ok_or_else(not_found)?;
+        let asset_id_ty = registry
+            .resolve(asset_id_option.ty.id)
+            .and_then(|option| option.type_params.first())
+            .and_then(|param| param.ty)
+            .ok_or(MetadataError::TypeNotFound(asset_id_option.ty.id))?;
+
+        let mut out = vec![];
+        encode::encode_value(registry, asset_id_ty.id, fee_asset_id, &mut out)?;
+        Ok(Encoded(out))
+    }
+
+    /// Create signed extrinsic, with its signed extensions set from `options`.
     pub fn create_signed(
         &self,
         signer: &str,
         pallet_name: &str,
         call_name: &str,
-        call_params: &str,
+        call_params: &serde_json::Value,
+        options: &SignOptions,
     ) -> Result<Vec<u8>, Error> {
-        let account_id = AccountId32::from_ss58check(&signer)
-            .expect("must be a valid ss58check format");
+        let scheme = SignatureScheme::of_signer(signer)?;
 
         // 1. SCALE encode call data to bytes (pallet u8, call u8, call params).
         let call_data =
-            self.encode_call_data(&pallet_name, &call_name, &call_params)?;
+            self.encode_call_data(pallet_name, call_name, call_params)?;
 
         // 2. Construct our custom additional/extra params.
-        let additional_and_extra_params = self.construct_params(&account_id)?;
+        let additional_and_extra_params =
+            self.construct_params(signer, options)?;
 
         // 3. Build extrinsic, now that we have the parts we need. This is compatible
         //    with the Encode impl for UncheckedExtrinsic (protocol version 4).
-        Ok(
-            ExtrinsicBuilder::new(call_data, additional_and_extra_params)
-                .build(account_id),
+        ExtrinsicBuilder::new(call_data, additional_and_extra_params).build(
+            &self.metadata,
+            signer,
+            scheme,
+            options.sign_raw,
         )
     }
 }
+
+/// SCALE encode `address` as the key of the `System.Account` map, with the account type
+/// of the chain such as an `AccountId32` or the `AccountId20` of Ethereum compatible chains.
+fn account_keys(
+    metadata: &Metadata,
+    address: &str,
+) -> Result<Vec<Vec<u8>>, Error> {
+    storage_api::encode_storage_map_keys(
+        metadata,
+        "System",
+        "Account",
+        &[serde_json::Value::String(address.to_string())],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::BaseApi,
+        types::test_metadata::{self, AccountId20, EthereumSignature},
+    };
+    use serde_json::json;
+    use sp_version::RuntimeVersion;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    /// An api over `metadata`, for the methods which fetch nothing from the chain.
+    fn api(metadata: Metadata) -> Api {
+        Api {
+            base_api: BaseApi::new("http://0.0.0.0:9933"),
+            metadata,
+            genesis_hash: H256::repeat_byte(1),
+            runtime_version: RuntimeVersion::default(),
+            at: None,
+        }
+    }
+
+    #[test]
+    fn test_encode_fee_asset_id() {
+        let api = api(test_metadata::metadata());
+        // the `Option<u32>` asset id of the `ChargeAssetTxPayment` extension
+        assert_eq!(
+            api.encode_fee_asset_id(&json!(1984)).unwrap().0,
+            1984u32.encode()
+        );
+        assert!(matches!(
+            api.encode_fee_asset_id(&json!("USDT")),
+            Err(Error::EncodeError(_))
+        ));
+    }
+
+    #[test]
+    fn test_nonce_account_keys() {
+        let alice = AccountId32::from_ss58check(ALICE).unwrap();
+        assert_eq!(
+            account_keys(&test_metadata::metadata(), ALICE).unwrap(),
+            [alice.encode()]
+        );
+
+        let metadata = test_metadata::metadata_with::<
+            AccountId20,
+            AccountId20,
+            EthereumSignature,
+        >();
+        let address = format!("0x{}", hex::encode([3; 20]));
+        assert_eq!(
+            account_keys(&metadata, &address).unwrap(),
+            [AccountId20([3; 20]).encode()]
+        );
+        // an SS58 address is no 20 bytes account
+        assert!(account_keys(&metadata, ALICE).is_err());
+    }
+}
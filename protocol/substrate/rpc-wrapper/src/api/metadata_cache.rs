@@ -0,0 +1,135 @@
+//!
+//! Metadata cache
+//!
+//! Keeps the decoded chain metadata for the lifetime of the wasm instance, and its SCALE
+//! encoding across invocations in the optional key-value store provided by the host.
+//! Entries are keyed by genesis hash and spec version, so a runtime upgrade is always a
+//! cache miss. The genesis hash of each node url is kept too, as it never changes.
+//!
+
+use crate::{
+    types::metadata::Metadata,
+    wrap::{imported::metadata_cache_module, MetadataCacheModule},
+};
+use codec::Decode;
+use frame_metadata::RuntimeMetadataPrefixed;
+use sp_core::H256;
+use std::{cell::RefCell, collections::HashMap, convert::TryFrom};
+
+#[derive(Default)]
+struct Memo {
+    genesis_hashes: HashMap<String, H256>,
+    metadata: HashMap<(H256, u32), Metadata>,
+}
+
+thread_local! {
+    static MEMO: RefCell<Memo> = RefCell::new(Memo::default());
+}
+
+fn host_key(genesis_hash: H256, spec_version: u32) -> String {
+    format!("substrate-metadata/{:#x}/{}", genesis_hash, spec_version)
+}
+
+/// Return the genesis hash of the chain of the node at `url`, if already fetched.
+pub fn genesis_hash(url: &str) -> Option<H256> {
+    MEMO.with(|memo| memo.borrow().genesis_hashes.get(url).copied())
+}
+
+/// Remember the `genesis_hash` of the chain of the node at `url`.
+pub fn insert_genesis_hash(url: &str, genesis_hash: H256) {
+    MEMO.with(|memo| {
+        memo.borrow_mut()
+            .genesis_hashes
+            .insert(url.to_string(), genesis_hash)
+    });
+}
+
+/// Return the cached metadata of runtime `spec_version` of the chain `genesis_hash`.
+pub fn get(genesis_hash: H256, spec_version: u32) -> Option<Metadata> {
+    if let Some(metadata) = memo_get(genesis_hash, spec_version) {
+        return Some(metadata);
+    }
+
+    // the host cache is optional, failing to reach it is just a cache miss
+    let bytes = MetadataCacheModule::get(&metadata_cache_module::ArgsGet {
+        key: host_key(genesis_hash, spec_version),
+    })
+    .ok()
+    .flatten()?;
+    let rt_metadata =
+        RuntimeMetadataPrefixed::decode(&mut bytes.as_slice()).ok()?;
+    let metadata = Metadata::try_from(rt_metadata).ok()?;
+    memo_insert(genesis_hash, spec_version, &metadata);
+    Some(metadata)
+}
+
+/// Cache the metadata of runtime `spec_version` of the chain `genesis_hash`.
+///
+/// `encoded` is the SCALE encoded [`RuntimeMetadataPrefixed`] served by the chain, from
+/// which `metadata` was decoded.
+pub fn insert(
+    genesis_hash: H256,
+    spec_version: u32,
+    encoded: Vec<u8>,
+    metadata: &Metadata,
+) {
+    let _ = MetadataCacheModule::set(&metadata_cache_module::ArgsSet {
+        key: host_key(genesis_hash, spec_version),
+        value: encoded,
+    });
+    memo_insert(genesis_hash, spec_version, metadata);
+}
+
+fn memo_get(genesis_hash: H256, spec_version: u32) -> Option<Metadata> {
+    MEMO.with(|memo| {
+        memo.borrow()
+            .metadata
+            .get(&(genesis_hash, spec_version))
+            .cloned()
+    })
+}
+
+fn memo_insert(genesis_hash: H256, spec_version: u32, metadata: &Metadata) {
+    MEMO.with(|memo| {
+        memo.borrow_mut()
+            .metadata
+            .insert((genesis_hash, spec_version), metadata.clone())
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_metadata;
+
+    const URL: &str = "http://0.0.0.0:9933";
+
+    #[test]
+    fn test_memo_hit_and_miss() {
+        let genesis_hash = H256::repeat_byte(1);
+        assert!(memo_get(genesis_hash, 100).is_none());
+
+        memo_insert(genesis_hash, 100, &test_metadata::metadata());
+        // a hit never reaches the host
+        assert!(get(genesis_hash, 100).is_some());
+        assert!(memo_get(H256::repeat_byte(2), 100).is_none());
+    }
+
+    #[test]
+    fn test_spec_version_bump_misses() {
+        let genesis_hash = H256::repeat_byte(1);
+        memo_insert(genesis_hash, 100, &test_metadata::metadata());
+
+        assert!(memo_get(genesis_hash, 101).is_none());
+        memo_insert(genesis_hash, 101, &test_metadata::metadata());
+        assert!(memo_get(genesis_hash, 101).is_some());
+    }
+
+    #[test]
+    fn test_genesis_hash_by_url() {
+        assert_eq!(genesis_hash(URL), None);
+        insert_genesis_hash(URL, H256::repeat_byte(1));
+        assert_eq!(genesis_hash(URL), Some(H256::repeat_byte(1)));
+        assert_eq!(genesis_hash("http://0.0.0.0:9944"), None);
+    }
+}
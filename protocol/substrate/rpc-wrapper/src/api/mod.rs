@@ -1,14 +1,22 @@
 use crate::{Error, Metadata};
 pub use base_api::BaseApi;
+use codec::Encode;
 use delegate::delegate;
+pub use extrinsic_api::SignOptions;
 use serde::de::DeserializeOwned;
 use sp_core::H256;
 use sp_runtime::traits::Header;
 use sp_version::RuntimeVersion;
+use std::convert::TryFrom;
+pub use storage_api::{identify_storage_key, storage_key_from_json};
 
 mod base_api;
+mod block_api;
+mod child_storage_api;
 mod constant_api;
+mod events_api;
 mod extrinsic_api;
+mod metadata_cache;
 mod storage_api;
 
 /// Api adds additional state to a BaseApi so that
@@ -22,6 +30,8 @@ pub struct Api {
     pub genesis_hash: H256,
     /// The runtime version of the blockchain
     pub runtime_version: RuntimeVersion,
+    /// The block the storage is read at, the best block if `None`
+    pub at: Option<H256>,
 }
 
 impl Api {
@@ -29,6 +39,14 @@ impl Api {
     delegate! {
         to self.base_api {
 
+            pub fn fetch_block_hash(&self, n: u32) -> Result<Option<H256>, Error>;
+
+            pub fn resolve_block_hash(
+                &self,
+                hash: Option<&str>,
+                number: Option<u32>,
+            ) -> Result<Option<H256>, Error>;
+
             #[call(fetch_finalized_head)]
             pub fn chain_get_finalized_head(&self) -> Result<Option<H256>, Error>;
 
@@ -46,26 +64,59 @@ impl Api {
 
     /// Try to create an instance of this api
     /// where it fetch metadata, the genesis_hash and runtime_version
+    ///
+    /// The metadata is taken from the [`metadata_cache`] when it has an entry for the
+    /// genesis hash and the spec version of the current runtime. Once the genesis hash of
+    /// `url` is cached, a cache hit only fetches the runtime version.
     pub fn new(url: &str) -> Result<Self, Error> {
+        Self::new_at(url, None)
+    }
+
+    /// Create an instance of this api reading the chain state as of block `at`, with the
+    /// runtime version and metadata which were active at that block.
+    pub fn new_at(url: &str, at: Option<H256>) -> Result<Self, Error> {
         let base_api = BaseApi::new(url);
-        let metadata = match base_api.fetch_metadata()? {
-            Some(metadata) => metadata,
-            None => return Err(Error::NoMetadata),
-        };
-        let genesis_hash = match base_api.fetch_genesis_hash()? {
+        let genesis_hash = match metadata_cache::genesis_hash(url) {
             Some(genesis_hash) => genesis_hash,
-            None => return Err(Error::NoGenesisHash),
+            None => match base_api.fetch_genesis_hash()? {
+                Some(genesis_hash) => {
+                    metadata_cache::insert_genesis_hash(url, genesis_hash);
+                    genesis_hash
+                }
+                None => return Err(Error::NoGenesisHash),
+            },
         };
-        let runtime_version = match base_api.fetch_runtime_version()? {
+        let runtime_version = match base_api.fetch_runtime_version_at(at)? {
             Some(runtime_version) => runtime_version,
             None => return Err(Error::NoRuntimeVersion),
         };
+        let spec_version = runtime_version.spec_version;
+        let metadata = match metadata_cache::get(genesis_hash, spec_version) {
+            Some(metadata) => metadata,
+            None => {
+                let rt_metadata =
+                    match base_api.fetch_runtime_metadata_at(at)? {
+                        Some(rt_metadata) => rt_metadata,
+                        None => return Err(Error::NoMetadata),
+                    };
+                let encoded = rt_metadata.encode();
+                let metadata = Metadata::try_from(rt_metadata)?;
+                metadata_cache::insert(
+                    genesis_hash,
+                    spec_version,
+                    encoded,
+                    &metadata,
+                );
+                metadata
+            }
+        };
 
         Ok(Self {
             base_api,
             metadata,
             genesis_hash,
             runtime_version,
+            at,
         })
     }
 }
@@ -4,10 +4,39 @@
 //! Extension to the API for retrieving data from on-chain storage
 //!
 
-use crate::{api::Api, utils::FromHexStr, Error};
+use crate::{
+    api::Api,
+    types::{
+        decode, encode,
+        metadata::{Metadata, MetadataError, StorageMapType},
+        read_proof, storage,
+    },
+    utils::{Encoded, FromHexStr},
+    Error,
+};
 use codec::{Decode, Encode};
-use scale_info::{form::PortableForm, Type};
-use sp_core::storage::StorageKey;
+use frame_metadata::v14::{StorageEntryModifier, StorageEntryType};
+use sp_core::{storage::StorageKey, H256};
+use sp_runtime::{generic::Header, traits::BlakeTwo256};
+use std::collections::HashMap;
+
+/// A storage key with its value in bytes, `None` when it has no value.
+pub type OpaqueStorageEntry = (StorageKey, Option<Vec<u8>>);
+
+/// The block and state root a read proof was verified against, with the verified values.
+pub type VerifiedStorage = (H256, H256, Vec<Option<Vec<u8>>>);
+
+/// A block where a storage value changed, with the new value in bytes, `None` when removed.
+pub type OpaqueStorageChange = (H256, Option<Vec<u8>>);
+
+/// The number of blocks of each `state_queryStorage` request, nodes failing on too long
+/// ranges.
+pub const QUERY_STORAGE_CHUNK: u32 = 1000;
+
+/// The pallet and storage entry names a storage key belongs to, with the keys recovered
+/// from it, see [`decode_storage_map_keys`].
+pub type IdentifiedStorageKey =
+    (String, String, Vec<Option<serde_json::Value>>);
 
 impl Api {
     /// Retrieve a value from storage value named `storage_name` in pallet `module`.
@@ -21,7 +50,7 @@ impl Api {
     {
         let storage_key =
             self.metadata.storage_value_key(module, storage_name)?;
-        self.fetch_storage_by_key_hash(storage_key)
+        self.fetch_storage_or_default(module, storage_name, storage_key)
     }
 
     /// Retrieve a value from a storage map named `storage_name` with a key `key` in pallet `module`
@@ -37,7 +66,7 @@ impl Api {
     {
         let storage_key =
             self.metadata.storage_map_key(module, storage_name, key)?;
-        self.fetch_storage_by_key_hash(storage_key)
+        self.fetch_storage_or_default(module, storage_name, storage_key)
     }
 
     /// Retrieve a value from a storage double map named `storage_name` with keys `first` and
@@ -60,7 +89,7 @@ impl Api {
             first,
             second,
         )?;
-        self.fetch_storage_by_key_hash(storage_key)
+        self.fetch_storage_or_default(module, storage_name, storage_key)
     }
 
     pub fn fetch_storage_by_key_hash<V>(
@@ -76,6 +105,110 @@ impl Api {
         }
     }
 
+    /// Decode the value at `storage_key` of storage `storage_name` in pallet `module`, or its
+    /// default value when absent, see [`Api::fetch_opaque_storage_or_default`].
+    fn fetch_storage_or_default<V>(
+        &self,
+        module: &str,
+        storage_name: &str,
+        storage_key: StorageKey,
+    ) -> Result<Option<V>, Error>
+    where
+        V: Decode,
+    {
+        match self.fetch_opaque_storage_or_default(
+            module,
+            storage_name,
+            storage_key,
+        )? {
+            Some((bytes, _)) => {
+                Ok(Some(Decode::decode(&mut bytes.as_slice())?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the value in bytes at `storage_key` of storage `storage_name` in pallet
+    /// `module`, with whether it is stored on chain. When absent, the value of a `Default`
+    /// entry is its default value from the metadata, and only an `Optional` entry has none.
+    pub fn fetch_opaque_storage_or_default(
+        &self,
+        module: &str,
+        storage_name: &str,
+        storage_key: StorageKey,
+    ) -> Result<Option<(Vec<u8>, bool)>, Error> {
+        match self.fetch_opaque_storage_by_key_hash(storage_key)? {
+            Some(bytes) => Ok(Some((bytes, true))),
+            None => Ok(self
+                .storage_default(module, storage_name)?
+                .map(|bytes| (bytes, false))),
+        }
+    }
+
+    /// The default value in bytes of storage `storage_name` in pallet `module`, `None` for
+    /// an `Optional` entry which has no default.
+    pub fn storage_default(
+        &self,
+        module: &str,
+        storage_name: &str,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let storage = self.metadata.pallet(module)?.storage(storage_name)?;
+        match storage.modifier {
+            StorageEntryModifier::Default => Ok(Some(storage.default.clone())),
+            StorageEntryModifier::Optional => Ok(None),
+        }
+    }
+
+    /// Decode the storage `bytes` of `storage_name` in pallet `module` into json, using
+    /// the value type from the metadata.
+    pub fn decode_storage_value(
+        &self,
+        module: &str,
+        storage_name: &str,
+        bytes: &[u8],
+    ) -> Result<serde_json::Value, Error> {
+        let type_id =
+            self.metadata.storage_value_type_id(module, storage_name)?;
+        let registry = self.metadata.type_registry()?;
+        Ok(decode::decode_value(registry, type_id, bytes)?)
+    }
+
+    /// SCALE encode the json `key` of storage map `storage_name` in pallet `module`, using
+    /// the key type from the metadata.
+    pub fn encode_storage_map_key(
+        &self,
+        module: &str,
+        storage_name: &str,
+        key: &serde_json::Value,
+    ) -> Result<Encoded, Error> {
+        let storage = self.metadata.pallet(module)?.storage(storage_name)?;
+        let key_type = match &storage.ty {
+            StorageEntryType::Map { key, .. } => key.id,
+            StorageEntryType::Plain(_) => {
+                return Err(MetadataError::StorageTypeError.into())
+            }
+        };
+        let mut out = vec![];
+        encode::encode_value(
+            self.metadata.type_registry()?,
+            key_type,
+            key,
+            &mut out,
+        )?;
+        Ok(Encoded(out))
+    }
+
+    /// SCALE encode the json `keys` of storage map `storage_name` in pallet `module`, each
+    /// with the type of its key from the metadata. There can be fewer keys than the map has.
+    pub fn encode_storage_map_keys(
+        &self,
+        module: &str,
+        storage_name: &str,
+        keys: &[serde_json::Value],
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        encode_storage_map_keys(&self.metadata, module, storage_name, keys)
+    }
+
     /// Retrieve a value in bytes from storage value named `storage_name` in pallet `module`.
     pub fn fetch_opaque_storage_value(
         &self,
@@ -84,7 +217,9 @@ impl Api {
     ) -> Result<Option<Vec<u8>>, Error> {
         let storage_key =
             self.metadata.storage_value_key(module, storage_name)?;
-        self.fetch_opaque_storage_by_key_hash(storage_key)
+        Ok(self
+            .fetch_opaque_storage_or_default(module, storage_name, storage_key)?
+            .map(|(bytes, _)| bytes))
     }
 
     /// Retrieve a value in bytes from a storage map named `storage_name` with a key `key` in pallet `module`
@@ -98,17 +233,45 @@ impl Api {
         K: Encode,
     {
         let storage_key =
-            self.metadata.storage_map_key(module, storage_name, key);
-        self.fetch_opaque_storage_by_key_hash(storage_key.unwrap())
+            self.metadata.storage_map_key(module, storage_name, key)?;
+        Ok(self
+            .fetch_opaque_storage_or_default(module, storage_name, storage_key)?
+            .map(|(bytes, _)| bytes))
     }
 
-    fn fetch_opaque_storage_by_key_hash(
+    /// Retrieve a value in bytes from a storage map named `storage_name` in pallet `module`
+    /// with any number of keys, given SCALE encoded in `keys`.
+    pub fn fetch_opaque_storage_n_map(
+        &self,
+        module: &str,
+        storage_name: &str,
+        keys: &[Vec<u8>],
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let storage_key =
+            storage_n_map_full_key(&self.metadata, module, storage_name, keys)?;
+        Ok(self
+            .fetch_opaque_storage_or_default(module, storage_name, storage_key)?
+            .map(|(bytes, _)| bytes))
+    }
+
+    /// Build the key of the entry of storage `storage_name` in pallet `module` from the json
+    /// `keys`: none for a storage value, and one per hasher for a map.
+    pub fn storage_key_from_json(
+        &self,
+        module: &str,
+        storage_name: &str,
+        keys: &[serde_json::Value],
+    ) -> Result<StorageKey, Error> {
+        storage_key_from_json(&self.metadata, module, storage_name, keys)
+    }
+
+    pub(crate) fn fetch_opaque_storage_by_key_hash(
         &self,
         storage_key: StorageKey,
     ) -> Result<Option<Vec<u8>>, Error> {
         let value = self
             .base_api
-            .json_request_value("state_getStorage", [storage_key])?;
+            .json_request_value("state_getStorage", (storage_key, self.at))?;
 
         match value {
             Some(value) => {
@@ -143,19 +306,225 @@ impl Api {
                 start_key,
             )?;
 
-        if let Some(storage_keys) = storage_keys {
-            let mut storage_values = Vec::with_capacity(storage_keys.len());
-            for storage_key in storage_keys.into_iter() {
-                if let Some(bytes) =
-                    self.fetch_opaque_storage_by_key_hash(storage_key)?
-                {
-                    storage_values.push(bytes);
+        storage_keys
+            .map(|storage_keys| self.fetch_opaque_storage_values(storage_keys))
+            .transpose()
+    }
+
+    /// Retrieve a paged list of values in bytes from a storage map named `storage_name`
+    /// in pallet `module`, of the entries whose keys start with the SCALE encoded `prefix_keys`.
+    /// `count` - the number of values to be returned
+    /// `start_keys` - the full keys of the entry to start after.
+    pub fn fetch_opaque_storage_n_map_paged(
+        &self,
+        module: &str,
+        storage_name: &str,
+        prefix_keys: &[Vec<u8>],
+        count: u32,
+        start_keys: Option<&[Vec<u8>]>,
+    ) -> Result<Option<Vec<Vec<u8>>>, Error> {
+        let prefix = self.metadata.storage_n_map_key(
+            module,
+            storage_name,
+            prefix_keys,
+        )?;
+        let start_key = start_keys
+            .map(|keys| {
+                self.metadata.storage_n_map_key(module, storage_name, keys)
+            })
+            .transpose()?;
+        self.fetch_opaque_storage_keys_paged_by_prefix(
+            prefix, count, start_key,
+        )?
+        .map(|storage_keys| self.fetch_opaque_storage_values(storage_keys))
+        .transpose()
+    }
+
+    /// Retrieve a page of the entries of a storage map named `storage_name` in pallet
+    /// `module` whose keys start with the SCALE encoded `prefix_keys`, with their storage key.
+    /// `count` - the number of storage keys to read
+    /// `start_key` - the storage key of the entry to start after.
+    /// The entries with no value are returned with `None`, so that the last storage key
+    /// continues the iteration.
+    pub fn fetch_opaque_storage_entries_paged(
+        &self,
+        module: &str,
+        storage_name: &str,
+        prefix_keys: &[Vec<u8>],
+        count: u32,
+        start_key: Option<StorageKey>,
+    ) -> Result<Vec<OpaqueStorageEntry>, Error> {
+        let prefix = self.metadata.storage_n_map_key(
+            module,
+            storage_name,
+            prefix_keys,
+        )?;
+        let storage_keys = self
+            .fetch_opaque_storage_keys_paged_by_prefix(
+                prefix, count, start_key,
+            )?
+            .unwrap_or_default();
+        let values = self.fetch_opaque_storage_multi(storage_keys.clone())?;
+        Ok(storage_keys.into_iter().zip(values).collect())
+    }
+
+    /// Recover the keys of an entry of storage map `storage_name` in pallet `module` from its
+    /// `storage_key`. The keys which can't be recovered from their hash are `None`, see
+    /// [`storage::decode_map_keys`].
+    pub fn decode_storage_map_keys(
+        &self,
+        module: &str,
+        storage_name: &str,
+        storage_key: &StorageKey,
+    ) -> Result<Vec<Option<serde_json::Value>>, Error> {
+        decode_storage_map_keys(
+            &self.metadata,
+            module,
+            storage_name,
+            storage_key,
+        )
+    }
+
+    /// Retrieve the values in bytes at `storage_keys`, skipping the keys with no value.
+    fn fetch_opaque_storage_values(
+        &self,
+        storage_keys: Vec<StorageKey>,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        Ok(self
+            .fetch_opaque_storage_multi(storage_keys)?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    /// Retrieve the values in bytes at `storage_keys` with a single `state_queryStorageAt`
+    /// request, in the order of the keys. Keys absent from the returned change sets give
+    /// `None`, a missing or malformed response is an error.
+    pub fn fetch_opaque_storage_multi(
+        &self,
+        storage_keys: Vec<StorageKey>,
+    ) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        if storage_keys.is_empty() {
+            return Ok(vec![]);
+        }
+        let value = self.base_api.json_request_value(
+            "state_queryStorageAt",
+            (&storage_keys, self.at),
+        )?;
+
+        // a missing result is a failed request, not keys without a value
+        let change_sets = match value {
+            Some(serde_json::Value::Array(change_sets)) => change_sets,
+            Some(value) => return Err(Error::ResponseJsonError(value)),
+            None => return Err(Error::NoResponse),
+        };
+        let mut values = HashMap::new();
+        for change_set in &change_sets {
+            for (storage_key, data) in parse_changes(change_set)? {
+                values.insert(storage_key.0, data);
+            }
+        }
+        Ok(storage_keys
+            .iter()
+            .map(|storage_key| values.get(&storage_key.0).cloned().flatten())
+            .collect())
+    }
+
+    /// Retrieve the values at `storage_keys` with a `state_getReadProof` proof at block
+    /// `block_hash`, and verify the proof against the state root of the header of the
+    /// block, checking that the header hashes to the block hash. The block is required, as
+    /// values verified against a block the node picks guarantee nothing.
+    ///
+    /// Returns the block, its state root and the verified values in the order of the keys.
+    pub fn fetch_verified_storage(
+        &self,
+        block_hash: H256,
+        storage_keys: Vec<StorageKey>,
+    ) -> Result<VerifiedStorage, Error> {
+        let value = self
+            .base_api
+            .json_request_value(
+                "state_getReadProof",
+                (&storage_keys, Some(block_hash)),
+            )?
+            .ok_or(Error::NoResponse)?;
+        // the proof must be of the block asked for
+        let block = match value["at"].as_str().map(H256::from_hex) {
+            Some(Ok(block)) if block == block_hash => block,
+            _ => return Err(Error::ResponseJsonError(value)),
+        };
+        let proof = value["proof"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|node| node.as_str().map(Vec::from_hex))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let header: Header<u32, BlakeTwo256> = self
+            .base_api
+            .fetch_header(block)?
+            .ok_or(Error::InvalidHeader(block))?;
+        if header.hash() != block {
+            return Err(Error::InvalidHeader(block));
+        }
+        let state_root = header.state_root;
+        let values = read_proof::verify_read_proof(
+            state_root,
+            self.runtime_version.state_version,
+            proof,
+            &storage_keys,
+        )?;
+        Ok((block, state_root, values))
+    }
+
+    /// Retrieve the blocks from number `from` to `to` where the value at `storage_key`
+    /// changed, with the value in bytes it changed to, `None` when it was removed. The
+    /// value at block `from` comes first.
+    ///
+    /// The range is queried with `state_queryStorage` in chunks of [`QUERY_STORAGE_CHUNK`]
+    /// blocks.
+    pub fn fetch_storage_changes(
+        &self,
+        storage_key: StorageKey,
+        from: u32,
+        to: u32,
+    ) -> Result<Vec<OpaqueStorageChange>, Error> {
+        let mut changes: Vec<OpaqueStorageChange> = vec![];
+        let mut start = from;
+        while start <= to {
+            let end = to.min(start.saturating_add(QUERY_STORAGE_CHUNK - 1));
+            let start_hash = self
+                .fetch_block_hash(start)?
+                .ok_or(Error::BlockNotFound(start))?;
+            let end_hash = self
+                .fetch_block_hash(end)?
+                .ok_or(Error::BlockNotFound(end))?;
+            let value = self.base_api.json_request_value(
+                "state_queryStorage",
+                ([&storage_key], start_hash, end_hash),
+            )?;
+
+            let change_sets = value.as_ref().and_then(|value| value.as_array());
+            for change_set in change_sets.into_iter().flatten() {
+                let block = match change_set["block"].as_str() {
+                    Some(block) => H256::from_hex(block)?,
+                    None => continue,
+                };
+                for (key, data) in parse_changes(change_set)? {
+                    // each chunk starts with the current value, even if unchanged
+                    let changed = changes.last().map(|(_, previous)| previous)
+                        != Some(&data);
+                    if key == storage_key && changed {
+                        changes.push((block, data));
+                    }
                 }
             }
-            Ok(Some(storage_values))
-        } else {
-            Ok(None)
+            if end == to {
+                break;
+            }
+            start = end + 1;
         }
+        Ok(changes)
     }
 
     /// return the data type of this storage named `storage_name` from pallet `module`.
@@ -163,7 +532,7 @@ impl Api {
         &self,
         module: &str,
         storage_name: &str,
-    ) -> Result<Option<(&Type<PortableForm>, &Type<PortableForm>)>, Error> {
+    ) -> Result<Option<StorageMapType<'_>>, Error> {
         Ok(self.metadata.storage_map_type(module, storage_name)?)
     }
 
@@ -193,28 +562,180 @@ impl Api {
         } else {
             None
         };
+        self.fetch_opaque_storage_keys_paged_by_prefix(
+            storage_key,
+            count,
+            start_storage_key,
+        )
+    }
+
+    /// Retrieve a paged list of the storage keys starting with `prefix`, after `start_key`
+    /// if given.
+    pub fn fetch_opaque_storage_keys_paged_by_prefix(
+        &self,
+        prefix: StorageKey,
+        count: u32,
+        start_key: Option<StorageKey>,
+    ) -> Result<Option<Vec<StorageKey>>, Error> {
         let value = self.base_api.json_request_value(
             "state_getKeysPaged",
-            (storage_key, count, start_storage_key),
+            (prefix, count, start_key, self.at),
         )?;
 
-        match value {
-            Some(value) => {
-                let value_array =
-                    value.as_array().expect("must be an array of str");
-                let data: Vec<StorageKey> = value_array
-                    .into_iter()
-                    .map(|v| {
-                        let value_str =
-                            v.as_str().expect("each item must be a str");
-                        let bytes = Vec::from_hex(value_str)
-                            .expect("must convert hex value to bytes");
-                        StorageKey(bytes)
-                    })
-                    .collect();
-                Ok(Some(data))
-            }
-            None => Ok(None),
+        Ok(value.map(parse_storage_keys))
+    }
+}
+
+/// SCALE encode the json `keys` of storage map `storage_name` in pallet `module`, each
+/// with the type of its key from the metadata. There can be fewer keys than the map has.
+pub fn encode_storage_map_keys(
+    metadata: &Metadata,
+    module: &str,
+    storage_name: &str,
+    keys: &[serde_json::Value],
+) -> Result<Vec<Vec<u8>>, Error> {
+    let storage = metadata.pallet(module)?.storage(storage_name)?;
+    let key_types = metadata.storage_key_type_ids(storage);
+    if key_types.is_empty() {
+        return Err(MetadataError::StorageTypeError.into());
+    }
+    if keys.len() > key_types.len() {
+        return Err(MetadataError::StorageKeyCount(
+            key_types.len(),
+            keys.len(),
+        )
+        .into());
+    }
+    let registry = metadata.type_registry()?;
+    let mut encoded = Vec::with_capacity(keys.len());
+    for (key, key_type) in keys.iter().zip(key_types) {
+        let mut out = vec![];
+        encode::encode_value(registry, key_type, key, &mut out)?;
+        encoded.push(out);
+    }
+    Ok(encoded)
+}
+
+/// Build the key of the entry of storage `storage_name` in pallet `module` from the json
+/// `keys`: none for a storage value, and one per hasher for a map.
+pub fn storage_key_from_json(
+    metadata: &Metadata,
+    module: &str,
+    storage_name: &str,
+    keys: &[serde_json::Value],
+) -> Result<StorageKey, Error> {
+    let storage = metadata.pallet(module)?.storage(storage_name)?;
+    match &storage.ty {
+        StorageEntryType::Plain(_) if keys.is_empty() => {
+            Ok(metadata.storage_value_key(module, storage_name)?)
+        }
+        StorageEntryType::Plain(_) => {
+            Err(MetadataError::StorageKeyCount(0, keys.len()).into())
         }
+        StorageEntryType::Map { .. } => {
+            let keys =
+                encode_storage_map_keys(metadata, module, storage_name, keys)?;
+            storage_n_map_full_key(metadata, module, storage_name, &keys)
+        }
+    }
+}
+
+/// Build the key of the entry of map `storage_name` in pallet `module` from the
+/// SCALE encoded `keys`, which must have one key per hasher of the map.
+fn storage_n_map_full_key(
+    metadata: &Metadata,
+    module: &str,
+    storage_name: &str,
+    keys: &[Vec<u8>],
+) -> Result<StorageKey, Error> {
+    let storage = metadata.pallet(module)?.storage(storage_name)?;
+    let key_count = metadata.storage_key_type_ids(storage).len();
+    if keys.len() != key_count {
+        return Err(
+            MetadataError::StorageKeyCount(key_count, keys.len()).into()
+        );
+    }
+    Ok(metadata.storage_n_map_key(module, storage_name, keys)?)
+}
+
+/// Recover the keys of an entry of storage map `storage_name` in pallet `module` from its
+/// `storage_key`. The keys which can't be recovered from their hash are `None`, see
+/// [`storage::decode_map_keys`].
+pub fn decode_storage_map_keys(
+    metadata: &Metadata,
+    module: &str,
+    storage_name: &str,
+    storage_key: &StorageKey,
+) -> Result<Vec<Option<serde_json::Value>>, Error> {
+    let storage = metadata.pallet(module)?.storage(storage_name)?;
+    let hashers = match &storage.ty {
+        StorageEntryType::Map { hashers, .. } => hashers,
+        StorageEntryType::Plain(_) => {
+            return Err(MetadataError::StorageTypeError.into())
+        }
+    };
+    let key_types = metadata.storage_key_type_ids(storage);
+    Ok(storage::decode_map_keys(
+        metadata.type_registry()?,
+        hashers,
+        &key_types,
+        &storage_key.0,
+    )?)
+}
+
+/// Find the storage entry of `storage_key` by the twox128 hashes of its pallet and storage
+/// names, and recover its map keys. A key of a whole map gives no keys, `None` if no entry
+/// matches.
+pub fn identify_storage_key(
+    metadata: &Metadata,
+    storage_key: &StorageKey,
+) -> Result<Option<IdentifiedStorageKey>, Error> {
+    let (pallet, storage) = match metadata.storage_entry_by_key(&storage_key.0)
+    {
+        Some(entry) => entry,
+        None => return Ok(None),
+    };
+    let keys = match &storage.ty {
+        _ if storage_key.0.len() == storage::PREFIX_LEN => vec![],
+        StorageEntryType::Plain(_) => return Ok(None),
+        StorageEntryType::Map { .. } => decode_storage_map_keys(
+            metadata,
+            &pallet.name,
+            &storage.name,
+            storage_key,
+        )?,
+    };
+    Ok(Some((pallet.name.clone(), storage.name.clone(), keys)))
+}
+
+/// Parse the hex storage keys of a `*_getKeysPaged` response.
+pub(super) fn parse_storage_keys(value: serde_json::Value) -> Vec<StorageKey> {
+    let value_array = value.as_array().expect("must be an array of str");
+    value_array
+        .iter()
+        .map(|v| {
+            let value_str = v.as_str().expect("each item must be a str");
+            let bytes = Vec::from_hex(value_str)
+                .expect("must convert hex value to bytes");
+            StorageKey(bytes)
+        })
+        .collect()
+}
+
+/// Parse the storage keys and values in bytes of the `changes` of a `StorageChangeSet`.
+fn parse_changes(
+    change_set: &serde_json::Value,
+) -> Result<Vec<OpaqueStorageEntry>, Error> {
+    let malformed = || Error::ResponseJsonError(change_set.clone());
+    let mut changes = vec![];
+    for change in change_set["changes"].as_array().ok_or_else(malformed)? {
+        let key = change[0].as_str().ok_or_else(malformed)?;
+        let data = match &change[1] {
+            serde_json::Value::String(data) => Some(Vec::from_hex(data)?),
+            serde_json::Value::Null => None,
+            _ => return Err(malformed()),
+        };
+        changes.push((StorageKey(Vec::from_hex(key)?), data));
     }
+    Ok(changes)
 }
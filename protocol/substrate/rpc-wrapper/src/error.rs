@@ -1,4 +1,5 @@
-use crate::types::metadata;
+use crate::types::{decode, encode, metadata, read_proof};
+use sp_core::H256;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -16,6 +17,10 @@ pub enum Error {
     NoGenesisHash,
     #[error("Unable to get chain Runtime version")]
     NoRuntimeVersion,
+    #[error("Unable to get chain finalized head")]
+    NoFinalizedHead,
+    #[error("Block {0} not found")]
+    BlockNotFound(u32),
     #[error("Metadata error: {0}")]
     MetadataError(#[from] metadata::MetadataError),
     #[error("Codec error: {0}")]
@@ -28,4 +33,26 @@ pub enum Error {
     SS58DecodingError(#[from] sp_core::crypto::PublicError),
     #[error("Could not decode BigInt into u128")]
     OversizedBigInt,
+    #[error("Error encoding json: {0}")]
+    EncodeError(#[from] encode::EncodeError),
+    #[error("Error decoding to json: {0}")]
+    DecodeError(#[from] decode::DecodeError),
+    #[error("Invalid dispatch error: {0}")]
+    InvalidDispatchError(serde_json::Value),
+    #[error("Error verifying storage proof: {0}")]
+    ReadProofError(#[from] read_proof::ReadProofError),
+    #[error("Header of block {0:#x} does not match its hash")]
+    InvalidHeader(H256),
+    #[error("Signed extension {0} is not supported")]
+    UnknownSignedExtension(String),
+    #[error("Chain has no signed extension {0}")]
+    SignedExtensionNotFound(String),
+    #[error("Signer provider error: {0}")]
+    SignerProviderError(String),
+    #[error("Account {0} is not managed by the signer provider")]
+    SignerNotFound(String),
+    #[error("Unsupported signer account type {0}")]
+    UnsupportedSignerType(String),
+    #[error("Invalid signature 0x{0} for the chain signature type")]
+    InvalidSignature(String),
 }
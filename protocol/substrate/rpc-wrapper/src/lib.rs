@@ -8,15 +8,25 @@
 pub mod wrap;
 
 pub use api::Api;
-use api::BaseApi;
+use api::{storage_key_from_json, BaseApi, SignOptions};
 
+use codec::Decode;
 pub use error::Error;
-use num_traits::cast::FromPrimitive;
-use polywrap_wasm_rs::BigNumber;
-use scale_info::{TypeDef, TypeDefPrimitive};
-use sp_core::crypto::{AccountId32, Ss58Codec};
+use frame_metadata::{v14::StorageEntryType, RuntimeMetadataPrefixed};
+use num_traits::cast::{FromPrimitive, ToPrimitive};
+use polywrap_wasm_rs::{BigInt, BigNumber};
+use scale_info::{form::PortableForm, Variant};
+use sp_core::{
+    crypto::{AccountId32, Ss58Codec},
+    storage::{PrefixedStorageKey, StorageKey},
+    H256,
+};
+use sp_runtime::generic::Era;
 
+use std::convert::TryFrom;
 pub use types::metadata::Metadata;
+use types::{child_storage, dispatch_error, events::Phase, metadata_diff};
+use utils::FromHexStr;
 use wrap::imported::*;
 pub use wrap::{
     imported::{
@@ -44,9 +54,11 @@ pub fn get_signer_provider_accounts(
 
 /// Retrieve the chain metadata via the `state_getMetadata` RPC
 pub fn chain_get_metadata(
-    ArgsChainGetMetadata { url }: ArgsChainGetMetadata,
+    ArgsChainGetMetadata { url, at, at_number }: ArgsChainGetMetadata,
 ) -> Option<ChainMetadata> {
-    let metadata = BaseApi::new(&url).fetch_metadata();
+    let api = BaseApi::new(&url);
+    let at = api.resolve_block_hash(at.as_deref(), at_number).ok()?;
+    let metadata = api.fetch_metadata_at(at);
     let meta = metadata.ok().flatten().expect("must have a metadata");
 
     let meta_json =
@@ -69,12 +81,153 @@ pub fn chain_get_metadata(
     })
 }
 
+/// return the pallets of the chain with their index, in the order of the metadata
+pub fn get_pallets(
+    ArgsGetPallets { url }: ArgsGetPallets,
+) -> Option<Vec<PalletInfo>> {
+    let api = Api::new(&url).ok()?;
+    Some(
+        api.metadata
+            .runtime_pallets()
+            .iter()
+            .map(|pallet| PalletInfo {
+                name: pallet.name.clone(),
+                index: pallet.index,
+                docs: pallet.docs.clone(),
+            })
+            .collect(),
+    )
+}
+
+/// return the calls of a pallet with their arguments
+///
+/// `pallet` the pallet or module the calls belong to.
+pub fn get_pallet_calls(
+    ArgsGetPalletCalls { url, pallet }: ArgsGetPalletCalls,
+) -> Option<Vec<VariantInfo>> {
+    let api = Api::new(&url).ok()?;
+    let calls = api.metadata.pallet_calls(&pallet).ok()?;
+    Some(variant_infos(&api.metadata, calls))
+}
+
+/// return the events of a pallet with their fields
+///
+/// `pallet` the pallet or module the events belong to.
+pub fn get_pallet_events(
+    ArgsGetPalletEvents { url, pallet }: ArgsGetPalletEvents,
+) -> Option<Vec<VariantInfo>> {
+    let api = Api::new(&url).ok()?;
+    let events = api.metadata.pallet_events(&pallet).ok()?;
+    Some(variant_infos(&api.metadata, events))
+}
+
+/// return the errors of a pallet
+///
+/// `pallet` the pallet or module the errors belong to.
+pub fn get_pallet_errors(
+    ArgsGetPalletErrors { url, pallet }: ArgsGetPalletErrors,
+) -> Option<Vec<VariantInfo>> {
+    let api = Api::new(&url).ok()?;
+    let errors = api.metadata.pallet_errors(&pallet).ok()?;
+    Some(variant_infos(&api.metadata, errors))
+}
+
+/// return the storage entries of a pallet with their kind, hashers and types
+///
+/// `pallet` the pallet or module the storage belongs to.
+pub fn get_pallet_storage(
+    ArgsGetPalletStorage { url, pallet }: ArgsGetPalletStorage,
+) -> Option<Vec<StorageEntryInfo>> {
+    let api = Api::new(&url).ok()?;
+    let metadata = &api.metadata;
+    let entries = metadata.pallet_storage_entries(&pallet).ok()?;
+
+    Some(
+        entries
+            .iter()
+            .map(|entry| {
+                let (kind, hashers, value_type) = match &entry.ty {
+                    StorageEntryType::Plain(value) => ("Plain", vec![], value),
+                    StorageEntryType::Map { hashers, value, .. } => {
+                        let kind = match hashers.len() {
+                            1 => "Map",
+                            2 => "DoubleMap",
+                            _ => "NMap",
+                        };
+                        (kind, hashers.clone(), value)
+                    }
+                };
+                StorageEntryInfo {
+                    name: entry.name.clone(),
+                    kind: kind.to_string(),
+                    hashers: hashers
+                        .iter()
+                        .map(|hasher| format!("{:?}", hasher))
+                        .collect(),
+                    key_types: metadata
+                        .storage_key_type_ids(entry)
+                        .into_iter()
+                        .map(|type_id| metadata.type_name(type_id))
+                        .collect(),
+                    value_type: metadata.type_name(value_type.id),
+                    modifier: format!("{:?}", entry.modifier),
+                    default: format!("0x{}", hex::encode(&entry.default)),
+                    docs: entry.docs.clone(),
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Compare two versions of the chain metadata, returning the pallets which changed.
+///
+/// Each version is read from the hex of the SCALE encoded metadata if given, otherwise
+/// from the chain as of the block hash, defaulting to the best block.
+pub fn metadata_diff(
+    ArgsMetadataDiff {
+        url,
+        old_block_hash,
+        old_metadata,
+        new_block_hash,
+        new_metadata,
+    }: ArgsMetadataDiff,
+) -> Option<Vec<PalletDiff>> {
+    let api = BaseApi::new(&url);
+    let old = load_metadata(&api, old_block_hash, old_metadata)?;
+    let new = load_metadata(&api, new_block_hash, new_metadata)?;
+
+    let item_diffs = |items: Vec<metadata_diff::ItemDiff>| {
+        items
+            .into_iter()
+            .map(|item| ItemDiff {
+                name: item.name,
+                change: format!("{:?}", item.change),
+                changes: item.changes,
+            })
+            .collect()
+    };
+    Some(
+        metadata_diff::diff(&old, &new)
+            .into_iter()
+            .map(|pallet| PalletDiff {
+                name: pallet.name,
+                change: format!("{:?}", pallet.change),
+                changes: pallet.changes,
+                calls: item_diffs(pallet.calls),
+                storage: item_diffs(pallet.storage),
+                events: item_diffs(pallet.events),
+            })
+            .collect(),
+    )
+}
+
 /// return the runtime version via the `state_getRuntimeVersion` RPC
 pub fn get_runtime_version(
-    ArgsGetRuntimeVersion { url }: ArgsGetRuntimeVersion,
+    ArgsGetRuntimeVersion { url, at, at_number }: ArgsGetRuntimeVersion,
 ) -> Option<RuntimeVersion> {
-    BaseApi::new(&url)
-        .fetch_runtime_version()
+    let api = BaseApi::new(&url);
+    let at = api.resolve_block_hash(at.as_deref(), at_number).ok()?;
+    api.fetch_runtime_version_at(at)
         .ok()
         .flatten()
         .map(|v| RuntimeVersion {
@@ -122,6 +275,116 @@ pub fn chain_get_block(
     block.ok().flatten().map(|block| BlockOutput { block })
 }
 
+/// return the Block at number, with its extrinsics decoded using the metadata of the
+/// runtime the block was produced with
+pub fn chain_get_decoded_block(
+    ArgsChainGetDecodedBlock { url, number }: ArgsChainGetDecodedBlock,
+) -> Option<DecodedBlock> {
+    let hash = BaseApi::new(&url).fetch_block_hash(number).ok()??;
+    let api = Api::new_at(&url, Some(hash)).ok()?;
+    let block = api.fetch_decoded_block(hash).ok()??;
+
+    Some(DecodedBlock {
+        hash: format!("{:#x}", block.hash),
+        number: block.number,
+        parent_hash: format!("{:#x}", block.parent_hash),
+        extrinsics: block
+            .extrinsics
+            .into_iter()
+            .map(|extrinsic| DecodedExtrinsic {
+                hash: format!("{:#x}", extrinsic.hash),
+                signed: extrinsic.signature.is_some(),
+                signature: extrinsic.signature.map(|signature| {
+                    ExtrinsicSignature {
+                        signer: signature.signer,
+                        signature_type: signature.signature_type,
+                        signature: signature.signature,
+                        mortality: match signature.era {
+                            Era::Immortal => None,
+                            Era::Mortal(period, phase) => Some(Mortality {
+                                period: period as u32,
+                                phase: phase as u32,
+                            }),
+                        },
+                        nonce: signature.nonce,
+                        tip: signature.tip,
+                        signed_extensions: JSON::Object(signature.extensions),
+                    }
+                }),
+                pallet: extrinsic.pallet,
+                call: extrinsic.call,
+                args: extrinsic.args,
+            })
+            .collect(),
+    })
+}
+
+/// return the events emitted in a block, decoded with the chain metadata
+///
+/// `block_hash` the hash of the block, takes precedence over `number`.
+/// `number` the number of the block. The best block is used when neither is given.
+pub fn get_events(
+    ArgsGetEvents {
+        url,
+        block_hash,
+        number,
+    }: ArgsGetEvents,
+) -> Option<Vec<EventRecord>> {
+    let api = api_at(&url, block_hash, number)?;
+    let events = api.fetch_events().ok()?;
+
+    Some(
+        events
+            .into_iter()
+            .map(|record| {
+                let (phase, extrinsic_index) = match record.phase {
+                    Phase::ApplyExtrinsic(index) => {
+                        ("ApplyExtrinsic", Some(index))
+                    }
+                    Phase::Finalization => ("Finalization", None),
+                    Phase::Initialization => ("Initialization", None),
+                };
+                EventRecord {
+                    phase: phase.to_string(),
+                    extrinsic_index,
+                    pallet: record.pallet,
+                    event: record.event,
+                    fields: record.fields,
+                    topics: record
+                        .topics
+                        .iter()
+                        .map(|topic| format!("{:#x}", topic))
+                        .collect(),
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Resolve a `DispatchError` into the name and docs of the error.
+///
+/// `dispatch_error` the hex of the SCALE encoded error, or the error as json such as the
+/// `dispatch_error` field of a `System.ExtrinsicFailed` event.
+/// `at`, `at_number` the block whose runtime returned the error, the best block if none.
+pub fn resolve_dispatch_error(
+    ArgsResolveDispatchError {
+        url,
+        dispatch_error,
+        at,
+        at_number,
+    }: ArgsResolveDispatchError,
+) -> Option<DispatchErrorInfo> {
+    let api = api_at(&url, at, at_number)?;
+    dispatch_error::resolve_dispatch_error(&api.metadata, &dispatch_error)
+        .ok()
+        .map(|info| DispatchErrorInfo {
+            kind: info.kind,
+            pallet: info.pallet,
+            error: info.error,
+            docs: info.docs,
+        })
+}
+
 /// return value of storage from a module and storage name
 ///
 /// `pallet` the pallet or module the storage belongs to.
@@ -131,9 +394,11 @@ pub fn get_storage_value(
         url,
         pallet,
         storage,
+        at,
+        at_number,
     }: ArgsGetStorageValue,
 ) -> Option<Vec<u8>> {
-    if let Ok(api) = Api::new(&url) {
+    if let Some(api) = api_at(&url, at, at_number) {
         api.fetch_opaque_storage_value(&pallet, &storage)
             .ok()
             .flatten()
@@ -142,6 +407,59 @@ pub fn get_storage_value(
     }
 }
 
+/// Decoded variant of `get_storage_value`, the value is returned as json.
+pub fn get_storage_value_decoded(
+    ArgsGetStorageValueDecoded {
+        url,
+        pallet,
+        storage,
+        at,
+        at_number,
+    }: ArgsGetStorageValueDecoded,
+) -> Option<JSON> {
+    let api = api_at(&url, at, at_number)?;
+    let bytes = api
+        .fetch_opaque_storage_value(&pallet, &storage)
+        .ok()
+        .flatten()?;
+    api.decode_storage_value(&pallet, &storage, &bytes).ok()
+}
+
+/// Get the value of any storage entry decoded into json, with whether it is stored on chain.
+/// The absent value of a `Default` entry is its default value, only `Optional` entries
+/// have no value.
+///
+/// `pallet` the pallet or module the storage belongs to.
+/// `storage` The storage identifier
+/// `keys` the keys of the entry, none for a storage value.
+pub fn get_storage(
+    ArgsGetStorage {
+        url,
+        pallet,
+        storage,
+        keys,
+        at,
+        at_number,
+    }: ArgsGetStorage,
+) -> Option<StorageRead> {
+    let api = api_at(&url, at, at_number)?;
+    let storage_key = api
+        .storage_key_from_json(
+            &pallet,
+            &storage,
+            keys.as_deref().unwrap_or_default(),
+        )
+        .ok()?;
+    let (bytes, stored) = api
+        .fetch_opaque_storage_or_default(&pallet, &storage, storage_key)
+        .ok()??;
+    Some(StorageRead {
+        value: api.decode_storage_value(&pallet, &storage, &bytes).ok()?,
+        raw_value: format!("0x{}", hex::encode(&bytes)),
+        stored,
+    })
+}
+
 /// Get the value in bytes from a storage map from a pallet with the specified `key`.
 ///
 /// `pallet` the pallet or module the storage belongs to.
@@ -153,52 +471,28 @@ pub fn get_storage_map(
         pallet,
         storage,
         key,
+        at,
+        at_number,
     }: ArgsGetStorageMap,
 ) -> Option<Vec<u8>> {
-    Api::new(&url)
-        .ok()
-        .map(|api| {
-            let (key_type, _value_type) = api
-                .storage_map_type(&pallet, &storage)
-                .ok()
-                .flatten()
-                .expect("must have a type");
-
-            if let TypeDef::Primitive(primitive) = key_type.type_def() {
-                //TODO: make this exhaustive to support i8,i16,i32,i64,i128 and u8,u16,u32,u64,u128
-                match primitive {
-                    TypeDefPrimitive::U32 => {
-                        assert!(key.is_number());
-                        let key =
-                            key.as_u64().expect("must cast to u64") as u32;
-                        api.fetch_opaque_storage_map(&pallet, &storage, key)
-                            .ok()
-                            .flatten()
-                    }
-                    TypeDefPrimitive::U64 => {
-                        assert!(key.is_number());
-                        let key =
-                            key.as_u64().expect("must cast to u64") as u64;
-                        api.fetch_opaque_storage_map(&pallet, &storage, key)
-                            .ok()
-                            .flatten()
-                    }
-                    TypeDefPrimitive::U128 => {
-                        assert!(key.is_number());
-                        let key =
-                            key.as_u64().expect("must cast to u64") as u128;
-                        api.fetch_opaque_storage_map(&pallet, &storage, key)
-                            .ok()
-                            .flatten()
-                    }
-                    _ => unimplemented!(),
-                }
-            } else {
-                //TODO: this should be error for UnSupported key type
-                None
-            }
-        })
-        .flatten()
+    api_at(&url, at, at_number)
+        .and_then(|api| fetch_storage_map(&api, &pallet, &storage, key))
+}
+
+/// Decoded variant of `get_storage_map`, the value is returned as json.
+pub fn get_storage_map_decoded(
+    ArgsGetStorageMapDecoded {
+        url,
+        pallet,
+        storage,
+        key,
+        at,
+        at_number,
+    }: ArgsGetStorageMapDecoded,
+) -> Option<JSON> {
+    let api = api_at(&url, at, at_number)?;
+    let bytes = fetch_storage_map(&api, &pallet, &storage, key)?;
+    api.decode_storage_value(&pallet, &storage, &bytes).ok()
 }
 
 /// Get a the storage value in bytes from a storage map of the specified pallet.
@@ -214,104 +508,572 @@ pub fn get_storage_map_paged(
         storage,
         count,
         next_to,
+        at,
+        at_number,
     }: ArgsGetStorageMapPaged,
 ) -> Option<Vec<Vec<u8>>> {
-    Api::new(&url)
-        .ok()
-        .map(|api| {
-            let (key_type, _value_type) = api
-                .storage_map_type(&pallet, &storage)
+    api_at(&url, at, at_number).and_then(|api| {
+        fetch_storage_map_paged(&api, &pallet, &storage, count, next_to)
+    })
+}
+
+/// Decoded variant of `get_storage_map_paged`, the values are returned as json.
+pub fn get_storage_map_paged_decoded(
+    ArgsGetStorageMapPagedDecoded {
+        url,
+        pallet,
+        storage,
+        count,
+        next_to,
+        at,
+        at_number,
+    }: ArgsGetStorageMapPagedDecoded,
+) -> Option<Vec<JSON>> {
+    let api = api_at(&url, at, at_number)?;
+    fetch_storage_map_paged(&api, &pallet, &storage, count, next_to)?
+        .iter()
+        .map(|bytes| api.decode_storage_value(&pallet, &storage, bytes).ok())
+        .collect()
+}
+
+/// Get the value in bytes from a storage double map from a pallet with the specified keys.
+///
+/// `pallet` the pallet or module the storage belongs to.
+/// `storage` The storage double map identifier
+/// `key1` The first key into the storage double map
+/// `key2` The second key into the storage double map
+pub fn get_storage_double_map(
+    ArgsGetStorageDoubleMap {
+        url,
+        pallet,
+        storage,
+        key1,
+        key2,
+        at,
+        at_number,
+    }: ArgsGetStorageDoubleMap,
+) -> Option<Vec<u8>> {
+    api_at(&url, at, at_number).and_then(|api| {
+        fetch_storage_n_map(&api, &pallet, &storage, &[key1, key2])
+    })
+}
+
+/// Get the value in bytes from a storage map with any number of keys from a pallet.
+///
+/// `pallet` the pallet or module the storage belongs to.
+/// `storage` The storage map identifier
+/// `keys` The keys into the storage map, one per hasher
+pub fn get_storage_n_map(
+    ArgsGetStorageNMap {
+        url,
+        pallet,
+        storage,
+        keys,
+        at,
+        at_number,
+    }: ArgsGetStorageNMap,
+) -> Option<Vec<u8>> {
+    api_at(&url, at, at_number)
+        .and_then(|api| fetch_storage_n_map(&api, &pallet, &storage, &keys))
+}
+
+/// Decoded variant of `get_storage_n_map`, the value is returned as json.
+pub fn get_storage_n_map_decoded(
+    ArgsGetStorageNMapDecoded {
+        url,
+        pallet,
+        storage,
+        keys,
+        at,
+        at_number,
+    }: ArgsGetStorageNMapDecoded,
+) -> Option<JSON> {
+    let api = api_at(&url, at, at_number)?;
+    let bytes = fetch_storage_n_map(&api, &pallet, &storage, &keys)?;
+    api.decode_storage_value(&pallet, &storage, &bytes).ok()
+}
+
+/// Get a page of the storage values in bytes from a storage map with any number of keys,
+/// of the entries starting with the given keys.
+///
+/// `pallet` the pallet or module the storage belongs to.
+/// `storage` The storage map identifier
+/// `keys` The first keys of the entries to return, may be empty to return all the entries
+/// `count` the number of items to be returned
+/// `next_to` optional full keys of the entry as a marker for the offset of the returned data.
+pub fn get_storage_n_map_paged(
+    ArgsGetStorageNMapPaged {
+        url,
+        pallet,
+        storage,
+        keys,
+        count,
+        next_to,
+        at,
+        at_number,
+    }: ArgsGetStorageNMapPaged,
+) -> Option<Vec<Vec<u8>>> {
+    api_at(&url, at, at_number).and_then(|api| {
+        fetch_storage_n_map_paged(
+            &api, &pallet, &storage, &keys, count, next_to,
+        )
+    })
+}
+
+/// Decoded variant of `get_storage_n_map_paged`, the values are returned as json.
+pub fn get_storage_n_map_paged_decoded(
+    ArgsGetStorageNMapPagedDecoded {
+        url,
+        pallet,
+        storage,
+        keys,
+        count,
+        next_to,
+        at,
+        at_number,
+    }: ArgsGetStorageNMapPagedDecoded,
+) -> Option<Vec<JSON>> {
+    let api = api_at(&url, at, at_number)?;
+    fetch_storage_n_map_paged(&api, &pallet, &storage, &keys, count, next_to)?
+        .iter()
+        .map(|bytes| api.decode_storage_value(&pallet, &storage, bytes).ok())
+        .collect()
+}
+
+/// Get a page of the entries of a storage map with their keys and decoded value.
+///
+/// `pallet` the pallet or module the storage belongs to.
+/// `storage` The storage map identifier
+/// `keys` optional first keys of the entries to return
+/// `count` the number of entries to read
+/// `cursor` the `cursor` of the previous page, to continue the iteration.
+pub fn get_storage_entries(
+    ArgsGetStorageEntries {
+        url,
+        pallet,
+        storage,
+        keys,
+        count,
+        cursor,
+        at,
+        at_number,
+    }: ArgsGetStorageEntries,
+) -> Option<StorageEntriesPage> {
+    let api = api_at(&url, at, at_number)?;
+    let keys = api
+        .encode_storage_map_keys(
+            &pallet,
+            &storage,
+            keys.as_deref().unwrap_or_default(),
+        )
+        .ok()?;
+    let cursor = cursor
+        .map(|cursor| Vec::from_hex(cursor).map(StorageKey))
+        .transpose()
+        .ok()?;
+    let page = api
+        .fetch_opaque_storage_entries_paged(
+            &pallet, &storage, &keys, count, cursor,
+        )
+        .ok()?;
+
+    // a page shorter than asked for is the last one
+    let cursor = match page.last() {
+        Some((storage_key, _)) if page.len() == count as usize => {
+            Some(format!("0x{}", hex::encode(&storage_key.0)))
+        }
+        _ => None,
+    };
+    let mut entries = Vec::with_capacity(page.len());
+    for (storage_key, bytes) in page {
+        let bytes = match bytes {
+            Some(bytes) => bytes,
+            None => continue,
+        };
+        let keys = api
+            .decode_storage_map_keys(&pallet, &storage, &storage_key)
+            .ok()?;
+        let key_recoverable = keys.iter().all(Option::is_some);
+        let key = match <[_; 1]>::try_from(keys) {
+            Ok([key]) => key,
+            Err(keys) => Some(JSON::from_iter(
+                keys.into_iter().map(|key| key.unwrap_or(JSON::Null)),
+            )),
+        };
+        entries.push(StorageEntry {
+            key,
+            key_recoverable,
+            raw_key: format!("0x{}", hex::encode(&storage_key.0)),
+            value: api.decode_storage_value(&pallet, &storage, &bytes).ok()?,
+        });
+    }
+    Some(StorageEntriesPage { entries, cursor })
+}
+
+/// Get the values of a list of storage entries from a storage proof, verified against the
+/// state root of the block, for reads from untrusted nodes.
+///
+/// `items` the pallet, storage and keys of each entry, as in `get_storage_multi`.
+/// Trusting the values comes down to trusting the hash of the block, given with `at`,
+/// which is why there is no block number nor best block to resolve through the node.
+pub fn get_storage_verified(
+    ArgsGetStorageVerified { url, items, at }: ArgsGetStorageVerified,
+) -> Option<VerifiedStorage> {
+    let api = api_at(&url, Some(at), None)?;
+    let storage_keys = storage_keys(&api, &items)?;
+    let (block, state_root, values) =
+        api.fetch_verified_storage(api.at?, storage_keys).ok()?;
+    let decoded = items
+        .iter()
+        .zip(&values)
+        .map(|(item, bytes)| match bytes {
+            Some(bytes) => api
+                .decode_storage_value(&item.pallet, &item.storage, bytes)
                 .ok()
-                .flatten()
-                .expect("must have a type");
-
-            if let TypeDef::Primitive(primitive) = key_type.type_def() {
-                //TODO: make this exhaustive to support i8,i16,i32,i64,i128 and u8,u16,u32,u64,u128
-                match primitive {
-                    TypeDefPrimitive::U32 => {
-                        let next_to = next_to
-                            .map(|k| k.as_u64().map(|k| k as u32))
-                            .flatten();
-                        api.fetch_opaque_storage_map_paged(
-                            &pallet, &storage, count, next_to,
-                        )
-                        .ok()
-                        .flatten()
-                    }
-                    TypeDefPrimitive::U64 => {
-                        let next_to = next_to
-                            .map(|k| k.as_u64().map(|k| k as u64))
-                            .flatten();
-                        api.fetch_opaque_storage_map_paged(
-                            &pallet, &storage, count, next_to,
-                        )
-                        .ok()
-                        .flatten()
-                    }
-                    TypeDefPrimitive::U128 => {
-                        let next_to = next_to
-                            .map(|k| k.as_u64().map(|k| k as u128))
-                            .flatten();
-                        api.fetch_opaque_storage_map_paged(
-                            &pallet, &storage, count, next_to,
-                        )
-                        .ok()
-                        .flatten()
-                    }
-                    _ => unimplemented!(),
-                }
-            } else {
-                None
-            }
+                .map(Some),
+            None => Some(None),
+        })
+        .collect::<Option<_>>()?;
+    Some(VerifiedStorage {
+        block: format!("{:#x}", block),
+        state_root: format!("{:#x}", state_root),
+        values: decoded,
+        raw_values: values
+            .into_iter()
+            .map(|bytes| bytes.map(|bytes| format!("0x{}", hex::encode(bytes))))
+            .collect(),
+    })
+}
+
+/// Get the blocks where a storage value changed in a range of blocks, with the value it
+/// changed to.
+///
+/// `pallet` the pallet or module the storage belongs to.
+/// `storage` The storage identifier
+/// `keys` the keys of the entry, none for a storage value.
+/// `from_block` the number of the first block, whose value is always returned.
+/// `to_block` the number of the last block. The key is built and the values decoded with the
+/// metadata of this block.
+pub fn storage_history(
+    ArgsStorageHistory {
+        url,
+        pallet,
+        storage,
+        keys,
+        from_block,
+        to_block,
+    }: ArgsStorageHistory,
+) -> Option<Vec<StorageChange>> {
+    let api = api_at(&url, None, Some(to_block))?;
+    let storage_key = api
+        .storage_key_from_json(
+            &pallet,
+            &storage,
+            keys.as_deref().unwrap_or_default(),
+        )
+        .ok()?;
+    let changes = api
+        .fetch_storage_changes(storage_key, from_block, to_block)
+        .ok()?;
+    changes
+        .into_iter()
+        .map(|(block, bytes)| {
+            let value = bytes
+                .as_ref()
+                .map(|bytes| api.decode_storage_value(&pallet, &storage, bytes))
+                .transpose()
+                .ok()?;
+            Some(StorageChange {
+                block: format!("{:#x}", block),
+                value,
+                raw_value: bytes
+                    .map(|bytes| format!("0x{}", hex::encode(bytes))),
+            })
+        })
+        .collect()
+}
+
+/// Get the values in bytes of a list of storage entries of any pallets, in a single request.
+///
+/// `items` the pallet, storage and keys of each entry, no keys for a storage value.
+/// The values are returned in the order of `items`, `None` for the entries with no value.
+pub fn get_storage_multi(
+    ArgsGetStorageMulti {
+        url,
+        items,
+        at,
+        at_number,
+    }: ArgsGetStorageMulti,
+) -> Option<Vec<Option<Vec<u8>>>> {
+    api_at(&url, at, at_number)
+        .and_then(|api| fetch_storage_multi(&api, &items))
+}
+
+/// Decoded variant of `get_storage_multi`, the values are returned as json.
+pub fn get_storage_multi_decoded(
+    ArgsGetStorageMultiDecoded {
+        url,
+        items,
+        at,
+        at_number,
+    }: ArgsGetStorageMultiDecoded,
+) -> Option<Vec<Option<JSON>>> {
+    let api = api_at(&url, at, at_number)?;
+    let values = fetch_storage_multi(&api, &items)?;
+    items
+        .iter()
+        .zip(values)
+        .map(|(item, bytes)| match bytes {
+            Some(bytes) => api
+                .decode_storage_value(&item.pallet, &item.storage, &bytes)
+                .ok()
+                .map(Some),
+            None => Some(None),
         })
+        .collect()
+}
+
+/// Get the value at `key` in a child trie, as hex.
+///
+/// `child` the child trie
+/// `key` hex of the key in the child trie
+pub fn get_child_storage(
+    ArgsGetChildStorage {
+        url,
+        child,
+        key,
+        at,
+        at_number,
+    }: ArgsGetChildStorage,
+) -> Option<String> {
+    let api = api_at(&url, at, at_number)?;
+    let key = StorageKey(Vec::from_hex(key).ok()?);
+    api.fetch_opaque_child_storage(&child_storage_key(child)?, &key)
+        .ok()
+        .flatten()
+        .map(|bytes| format!("0x{}", hex::encode(bytes)))
+}
+
+/// Get the hash of the value at `key` in a child trie.
+///
+/// `child` the child trie
+/// `key` hex of the key in the child trie
+pub fn get_child_storage_hash(
+    ArgsGetChildStorageHash {
+        url,
+        child,
+        key,
+        at,
+        at_number,
+    }: ArgsGetChildStorageHash,
+) -> Option<String> {
+    let api = api_at(&url, at, at_number)?;
+    let key = StorageKey(Vec::from_hex(key).ok()?);
+    api.fetch_child_storage_hash(&child_storage_key(child)?, &key)
+        .ok()
+        .flatten()
+        .map(|hash| format!("{:#x}", hash))
+}
+
+/// Get the size in bytes of the value at `key` in a child trie.
+///
+/// `child` the child trie
+/// `key` hex of the key in the child trie
+pub fn get_child_storage_size(
+    ArgsGetChildStorageSize {
+        url,
+        child,
+        key,
+        at,
+        at_number,
+    }: ArgsGetChildStorageSize,
+) -> Option<u64> {
+    let api = api_at(&url, at, at_number)?;
+    let key = StorageKey(Vec::from_hex(key).ok()?);
+    api.fetch_child_storage_size(&child_storage_key(child)?, &key)
+        .ok()
         .flatten()
 }
 
+/// Get a page of the keys of a child trie, as hex.
+///
+/// `child` the child trie
+/// `prefix` optional hex of the prefix of the keys to return
+/// `count` the number of keys to be returned
+/// `start_key` optional hex of the key as a marker for the offset of the returned keys.
+pub fn get_child_storage_keys_paged(
+    ArgsGetChildStorageKeysPaged {
+        url,
+        child,
+        prefix,
+        count,
+        start_key,
+        at,
+        at_number,
+    }: ArgsGetChildStorageKeysPaged,
+) -> Option<Vec<String>> {
+    let api = api_at(&url, at, at_number)?;
+    let prefix = prefix
+        .map(|prefix| Vec::from_hex(prefix).map(StorageKey))
+        .transpose()
+        .ok()?;
+    let start_key = start_key
+        .map(|start_key| Vec::from_hex(start_key).map(StorageKey))
+        .transpose()
+        .ok()?;
+    let keys = api
+        .fetch_child_storage_keys_paged(
+            &child_storage_key(child)?,
+            prefix,
+            count,
+            start_key,
+        )
+        .ok()
+        .flatten()?;
+    Some(
+        keys.iter()
+            .map(|key| format!("0x{}", hex::encode(&key.0)))
+            .collect(),
+    )
+}
+
+/// Compute the hex key of a storage entry from the metadata, without reading the storage.
+///
+/// `metadata` optional hex of the SCALE encoded metadata, fetched as of `block_hash` if `None`
+/// `keys` the keys of the entry as in `get_storage_multi`, no keys for a storage value.
+pub fn storage_key(
+    ArgsStorageKey {
+        url,
+        metadata,
+        block_hash,
+        pallet,
+        storage,
+        keys,
+    }: ArgsStorageKey,
+) -> Option<String> {
+    let metadata = load_metadata(&BaseApi::new(&url), block_hash, metadata)?;
+    let storage_key = storage_key_from_json(
+        &metadata,
+        &pallet,
+        &storage,
+        keys.as_deref().unwrap_or_default(),
+    )
+    .ok()?;
+    Some(format!("0x{}", hex::encode(storage_key.0)))
+}
+
+/// Find the pallet and storage entry of a hex storage key from the metadata, and decode
+/// the keys of the entry which can be recovered from their hash.
+///
+/// `metadata` optional hex of the SCALE encoded metadata, fetched as of `block_hash` if `None`
+/// Returns `None` when no storage entry matches the key.
+pub fn identify_storage_key(
+    ArgsIdentifyStorageKey {
+        url,
+        metadata,
+        block_hash,
+        key,
+    }: ArgsIdentifyStorageKey,
+) -> Option<IdentifiedStorageKey> {
+    let metadata = load_metadata(&BaseApi::new(&url), block_hash, metadata)?;
+    let storage_key = StorageKey(Vec::from_hex(key).ok()?);
+    let (pallet, storage, keys) =
+        api::identify_storage_key(&metadata, &storage_key).ok()??;
+    Some(IdentifiedStorageKey {
+        pallet,
+        storage,
+        keys,
+    })
+}
+
 /// return the constant value from a pallet
 ///
 /// `pallet` the pallet or module the storage belongs to.
 /// `name` Name of the constant
 pub fn constant(
-    ArgsConstant { url, pallet, name }: ArgsConstant,
+    ArgsConstant {
+        url,
+        pallet,
+        name,
+        at,
+        at_number,
+    }: ArgsConstant,
 ) -> Option<Vec<u8>> {
-    Api::new(&url)
-        .ok()
-        .map(|api| api.fetch_constant_opaque_value(&pallet, &name).ok())
-        .flatten()
+    api_at(&url, at, at_number)
+        .and_then(|api| api.fetch_constant_opaque_value(&pallet, &name).ok())
+}
+
+/// return the constant value from a pallet, decoded into json
+///
+/// `pallet` the pallet or module the constant belongs to.
+/// `name` Name of the constant
+pub fn constant_decoded(
+    ArgsConstantDecoded {
+        url,
+        pallet,
+        name,
+        at,
+        at_number,
+    }: ArgsConstantDecoded,
+) -> Option<JSON> {
+    api_at(&url, at, at_number)
+        .and_then(|api| api.fetch_constant_value_json(&pallet, &name).ok())
+}
+
+/// return all the constants of a pallet with their decoded value and docs
+///
+/// `pallet` the pallet or module the constants belong to. A constant which doesn't decode
+/// with its type in the metadata has no value, without failing the others.
+pub fn constants(
+    ArgsConstants {
+        url,
+        pallet,
+        at,
+        at_number,
+    }: ArgsConstants,
+) -> Option<Vec<PalletConstant>> {
+    let api = api_at(&url, at, at_number)?;
+    let constants = api.decode_pallet_constants(&pallet).ok()?;
+    Some(
+        constants
+            .into_iter()
+            .map(|(constant, value)| PalletConstant {
+                name: constant.name.clone(),
+                type_name: api.metadata.type_name(constant.ty.id),
+                value,
+                docs: constant.docs.clone(),
+            })
+            .collect(),
+    )
 }
 
 /// Get the account information of `account` by reading into the System crate Account storage
 ///
 /// `account` Account identifier to retrieve value of
 pub fn account_info(
-    ArgsAccountInfo { url, account }: ArgsAccountInfo,
+    ArgsAccountInfo {
+        url,
+        account,
+        at,
+        at_number,
+    }: ArgsAccountInfo,
 ) -> Option<AccountInfo> {
-    let account_id = AccountId32::from_string(&account)
-        .expect("must be a valid ss58check format");
-    let api = Api::new(&url).unwrap();
-    let account_info = api.get_account_info(&account_id).unwrap();
-
-    if let Some(account_info) = account_info {
-        Some(AccountInfo {
-            nonce: account_info.nonce,
-            consumers: account_info.consumers,
-            providers: account_info.providers,
-            sufficients: account_info.sufficients,
-            data: AccountData {
-                free: BigNumber::from_u128(account_info.data.free).unwrap(),
-                reserved: BigNumber::from_u128(account_info.data.reserved)
-                    .unwrap(),
-                misc_frozen: BigNumber::from_u128(
-                    account_info.data.misc_frozen,
-                )
-                .unwrap(),
-                fee_frozen: BigNumber::from_u128(account_info.data.fee_frozen)
-                    .unwrap(),
-            },
-        })
-    } else {
-        None
-    }
+    let account_id = AccountId32::from_string(&account).ok()?;
+    let api = api_at(&url, at, at_number)?;
+    let account_info = api.get_account_info(&account_id).ok()??;
+
+    Some(AccountInfo {
+        nonce: account_info.nonce,
+        consumers: account_info.consumers,
+        providers: account_info.providers,
+        sufficients: account_info.sufficients,
+        data: AccountData {
+            free: BigNumber::from_u128(account_info.data.free)?,
+            reserved: BigNumber::from_u128(account_info.data.reserved)?,
+            misc_frozen: BigNumber::from_u128(account_info.data.misc_frozen)?,
+            fee_frozen: BigNumber::from_u128(account_info.data.fee_frozen)?,
+        },
+    })
 }
 
 /// Get the `nonce` for this account.
@@ -324,8 +1086,7 @@ pub fn get_nonce_for_account(
         .expect("must be a valid ss58check format");
     Api::new(&url)
         .ok()
-        .map(|api| api.get_nonce_for_account(&account_id).ok())
-        .flatten()
+        .and_then(|api| api.get_nonce_for_account(&account_id).ok())
 }
 
 /// Get the index of the pallet and the call/function name.
@@ -335,15 +1096,12 @@ pub fn get_nonce_for_account(
 pub fn pallet_call_index(
     ArgsPalletCallIndex { url, pallet, call }: ArgsPalletCallIndex,
 ) -> Option<Vec<u8>> {
-    Api::new(&url)
-        .ok()
-        .map(|api| {
-            api.metadata
-                .pallet_call_index(&pallet, &call)
-                .ok()
-                .map(|v| v.to_vec())
-        })
-        .flatten()
+    Api::new(&url).ok().and_then(|api| {
+        api.metadata
+            .pallet_call_index(&pallet, &call)
+            .ok()
+            .map(|v| v.to_vec())
+    })
 }
 
 /// Create signed extrinsic.
@@ -354,11 +1112,21 @@ pub fn sign(
         pallet_name,
         call_name,
         call_params,
+        mortality,
+        tip,
+        fee_asset_id,
+        sign_raw,
     }: ArgsSign,
 ) -> Option<String> {
     let api = Api::new(&url).ok()?;
     let extrinsic = api
-        .create_signed(&signer, &pallet_name, &call_name, &call_params)
+        .create_signed(
+            &signer,
+            &pallet_name,
+            &call_name,
+            &call_params,
+            &sign_options(mortality, tip, fee_asset_id, sign_raw)?,
+        )
         .ok()?;
 
     Some(format!("0x{}", hex::encode(extrinsic)))
@@ -385,11 +1153,21 @@ pub fn sign_and_submit(
         pallet_name,
         call_name,
         call_params,
+        mortality,
+        tip,
+        fee_asset_id,
+        sign_raw,
     }: ArgsSignAndSubmit,
 ) -> Option<String> {
     let api = Api::new(&url).ok()?;
     let extrinsic = api
-        .create_signed(&signer, &pallet_name, &call_name, &call_params)
+        .create_signed(
+            &signer,
+            &pallet_name,
+            &call_name,
+            &call_params,
+            &sign_options(mortality, tip, fee_asset_id, sign_raw)?,
+        )
         .ok()?;
 
     api.author_submit_extrinsic(format!("0x{}", hex::encode(extrinsic)))
@@ -397,3 +1175,205 @@ pub fn sign_and_submit(
         .flatten()
         .map(|res| format!("{:#x}", res))
 }
+
+/// Collect the signed extension arguments of `sign` and `sign_and_submit`, `None` if the
+/// tip doesn't fit a `u128`.
+fn sign_options(
+    mortality: Option<u32>,
+    tip: Option<BigInt>,
+    fee_asset_id: Option<JSON>,
+    sign_raw: Option<bool>,
+) -> Option<SignOptions> {
+    let tip = match tip {
+        Some(tip) => Some(tip.to_u128()?),
+        None => None,
+    };
+    Some(SignOptions {
+        mortality: mortality.map(u64::from),
+        tip,
+        fee_asset_id,
+        sign_raw: sign_raw.unwrap_or_default(),
+    })
+}
+
+/// Create the api reading the chain state at the block given by its hash `at`, which takes
+/// precedence, or its number `at_number`. The best block is used when neither is given.
+fn api_at(
+    url: &str,
+    at: Option<String>,
+    at_number: Option<u32>,
+) -> Option<Api> {
+    let at = BaseApi::new(url)
+        .resolve_block_hash(at.as_deref(), at_number)
+        .ok()?;
+    Api::new_at(url, at).ok()
+}
+
+/// Fetch the bytes of the value at the json `key` in a storage map, with the key encoded
+/// according to the key type of the map.
+fn fetch_storage_map(
+    api: &Api,
+    pallet: &str,
+    storage: &str,
+    key: JSON,
+) -> Option<Vec<u8>> {
+    let key = api.encode_storage_map_key(pallet, storage, &key).ok()?;
+    api.fetch_opaque_storage_map(pallet, storage, key)
+        .ok()
+        .flatten()
+}
+
+/// Fetch a page of values in bytes from a storage map, with the `next_to` json key encoded
+/// according to the key type of the map.
+fn fetch_storage_map_paged(
+    api: &Api,
+    pallet: &str,
+    storage: &str,
+    count: u32,
+    next_to: Option<JSON>,
+) -> Option<Vec<Vec<u8>>> {
+    let next_to = next_to
+        .map(|key| api.encode_storage_map_key(pallet, storage, &key))
+        .transpose()
+        .ok()?;
+    api.fetch_opaque_storage_map_paged(pallet, storage, count, next_to)
+        .ok()
+        .flatten()
+}
+
+/// Fetch the bytes of the value at the json `keys` in a storage map with any number of keys,
+/// each key encoded according to its type in the map.
+fn fetch_storage_n_map(
+    api: &Api,
+    pallet: &str,
+    storage: &str,
+    keys: &[JSON],
+) -> Option<Vec<u8>> {
+    let keys = api.encode_storage_map_keys(pallet, storage, keys).ok()?;
+    api.fetch_opaque_storage_n_map(pallet, storage, &keys)
+        .ok()
+        .flatten()
+}
+
+/// Fetch a page of values in bytes from a storage map with any number of keys, of the
+/// entries starting with the json `keys`.
+fn fetch_storage_n_map_paged(
+    api: &Api,
+    pallet: &str,
+    storage: &str,
+    keys: &[JSON],
+    count: u32,
+    next_to: Option<Vec<JSON>>,
+) -> Option<Vec<Vec<u8>>> {
+    let keys = api.encode_storage_map_keys(pallet, storage, keys).ok()?;
+    let next_to = next_to
+        .map(|next_to| api.encode_storage_map_keys(pallet, storage, &next_to))
+        .transpose()
+        .ok()?;
+    api.fetch_opaque_storage_n_map_paged(
+        pallet,
+        storage,
+        &keys,
+        count,
+        next_to.as_deref(),
+    )
+    .ok()
+    .flatten()
+}
+
+/// Fetch the bytes of the values of the storage entries `items` with a single request.
+/// The absent values of `Default` entries are their default value.
+fn fetch_storage_multi(
+    api: &Api,
+    items: &[StorageQuery],
+) -> Option<Vec<Option<Vec<u8>>>> {
+    let values = api
+        .fetch_opaque_storage_multi(storage_keys(api, items)?)
+        .ok()?;
+    items
+        .iter()
+        .zip(values)
+        .map(|(item, bytes)| match bytes {
+            Some(bytes) => Some(Some(bytes)),
+            None => api.storage_default(&item.pallet, &item.storage).ok(),
+        })
+        .collect()
+}
+
+/// Build the storage key of each of the storage entries `items`.
+fn storage_keys(api: &Api, items: &[StorageQuery]) -> Option<Vec<StorageKey>> {
+    items
+        .iter()
+        .map(|item| {
+            api.storage_key_from_json(
+                &item.pallet,
+                &item.storage,
+                item.keys.as_deref().unwrap_or_default(),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .ok()
+}
+
+/// The storage key of the child trie given by one of the fields of `child`.
+fn child_storage_key(child: ChildStorageKey) -> Option<PrefixedStorageKey> {
+    match child {
+        ChildStorageKey {
+            storage_key: Some(storage_key),
+            ..
+        } => Some(PrefixedStorageKey::new(Vec::from_hex(storage_key).ok()?)),
+        ChildStorageKey {
+            trie_id: Some(trie_id),
+            ..
+        } => Some(child_storage::default_child_storage_key(
+            &Vec::from_hex(trie_id).ok()?,
+        )),
+        ChildStorageKey {
+            crowdloan_fund_index: Some(fund_index),
+            ..
+        } => Some(child_storage::crowdloan_child_storage_key(fund_index)),
+        _ => None,
+    }
+}
+
+fn variant_infos(
+    metadata: &Metadata,
+    variants: &[Variant<PortableForm>],
+) -> Vec<VariantInfo> {
+    variants
+        .iter()
+        .map(|variant| VariantInfo {
+            name: variant.name.clone(),
+            index: variant.index,
+            fields: variant
+                .fields
+                .iter()
+                .map(|field| FieldInfo {
+                    name: field.name.clone(),
+                    type_name: metadata.type_name(field.ty.id),
+                    type_id: field.ty.id,
+                })
+                .collect(),
+            docs: variant.docs.clone(),
+        })
+        .collect()
+}
+
+/// Decode the hex `metadata` if given, otherwise fetch the metadata as of `block_hash`.
+fn load_metadata(
+    api: &BaseApi,
+    block_hash: Option<String>,
+    metadata: Option<String>,
+) -> Option<Metadata> {
+    let rt_metadata = match metadata {
+        Some(metadata) => {
+            let bytes = Vec::from_hex(metadata).ok()?;
+            RuntimeMetadataPrefixed::decode(&mut bytes.as_slice()).ok()?
+        }
+        None => {
+            let at = block_hash.map(H256::from_hex).transpose().ok()?;
+            api.fetch_runtime_metadata_at(at).ok()??
+        }
+    };
+    Metadata::try_from(rt_metadata).ok()
+}
@@ -0,0 +1,56 @@
+//! A block with its extrinsics decoded using the metadata.
+
+use crate::{
+    types::extrinsic::{self, DecodedExtrinsic},
+    utils::FromHexStr,
+    Error, Metadata,
+};
+use sp_core::H256;
+
+#[derive(Clone, Debug)]
+pub struct DecodedBlock {
+    pub hash: H256,
+    pub number: u32,
+    pub parent_hash: H256,
+    pub extrinsics: Vec<DecodedExtrinsic>,
+}
+
+impl DecodedBlock {
+    /// Decode the json returned by `chain_getBlock` for the block `hash`.
+    pub fn from_json(
+        metadata: &Metadata,
+        hash: H256,
+        block: &serde_json::Value,
+    ) -> Result<Self, Error> {
+        let invalid = || Error::ResponseJsonError(block.clone());
+        let header = &block["block"]["header"];
+        let number = header["number"]
+            .as_str()
+            .and_then(|number| {
+                u32::from_str_radix(number.trim_start_matches("0x"), 16).ok()
+            })
+            .ok_or_else(invalid)?;
+        let parent_hash = header["parentHash"]
+            .as_str()
+            .map(H256::from_hex)
+            .transpose()?
+            .ok_or_else(invalid)?;
+
+        let extrinsics = block["block"]["extrinsics"]
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|hex| {
+                let bytes = Vec::from_hex(hex.as_str().ok_or_else(invalid)?)?;
+                extrinsic::decode_extrinsic(metadata, &bytes)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            hash,
+            number,
+            parent_hash,
+            extrinsics,
+        })
+    }
+}
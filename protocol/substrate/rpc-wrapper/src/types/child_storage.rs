@@ -0,0 +1,41 @@
+//! For querying the storage of child tries.
+//!
+//! This file is **not** from subxt.
+
+use codec::Encode;
+use sp_core::storage::{ChildInfo, PrefixedStorageKey};
+
+/// The storage key of the default child trie with id `trie_id`, such as the `trie_id` of
+/// a contract.
+pub fn default_child_storage_key(trie_id: &[u8]) -> PrefixedStorageKey {
+    ChildInfo::new_default(trie_id).prefixed_storage_key()
+}
+
+/// The storage key of the child trie of the contributions to the crowdloan fund
+/// `fund_index`, as derived by the `crowdloan` pallet.
+pub fn crowdloan_child_storage_key(fund_index: u32) -> PrefixedStorageKey {
+    let mut buf = b"crowdloan".to_vec();
+    buf.extend(fund_index.encode());
+    default_child_storage_key(&sp_core::blake2_256(&buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crowdloan_child_storage_key() {
+        // ":child_storage:default:" ++ blake2_256("crowdloan" ++ fund index), the
+        // `id_from_index` of the polkadot `crowdloan` pallet
+        assert_eq!(
+            hex::encode(crowdloan_child_storage_key(0).into_inner()),
+            "3a6368696c645f73746f726167653a64656661756c743a\
+             c40cac02c4ed0673d410e5a6fc91234cd1287902634e34ee2b379c4e8a7131ca"
+        );
+        assert_eq!(
+            hex::encode(crowdloan_child_storage_key(2000).into_inner()),
+            "3a6368696c645f73746f726167653a64656661756c743a\
+             e7f388407d372e0622cbfb257a1738279e02a030814b4422bd55be3f202c9e35"
+        );
+    }
+}
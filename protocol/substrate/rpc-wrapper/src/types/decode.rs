@@ -0,0 +1,524 @@
+//! Decode SCALE encoded bytes into json values using the types of the metadata type registry.
+//!
+//! The produced json mirrors what [`crate::types::encode`] accepts:
+//! - composite: an object keyed by field name, an array for unnamed fields, or the inner
+//!   value for single field wrappers.
+//! - variant: the variant name as a string when it has no fields, otherwise an object with
+//!   the variant name as its only key. `Option` is `null` or the value itself.
+//! - sequence and array: an array, or a `0x` hex string for `u8` elements.
+//! - tuple: an array, `null` for the unit type.
+//! - integers: a number, except for 128 and 256 bit integers which are decimal strings.
+//! - bit sequence: a string of `0` and `1`.
+//! - `AccountId32`: an SS58 address.
+//!
+//! This file is **not** from subxt.
+
+use codec::{Compact, Decode, Input};
+use scale_info::{
+    form::PortableForm, Field, PortableRegistry, Type, TypeDef,
+    TypeDefBitSequence, TypeDefPrimitive, TypeDefVariant,
+};
+use serde_json::{Map, Value};
+use sp_core::{
+    crypto::{AccountId32, Ss58Codec},
+    U256,
+};
+
+/// Error when the bytes can't be decoded as the expected type.
+#[derive(Debug, thiserror::Error)]
+#[error("{reason} at `{path}`")]
+pub struct DecodeError {
+    /// Location of the offending value, e.g `data.free`.
+    pub path: String,
+    pub reason: String,
+}
+
+/// Decode all of `bytes` as the type `type_id` of the `registry`.
+pub fn decode_value(
+    registry: &PortableRegistry,
+    type_id: u32,
+    bytes: &[u8],
+) -> Result<Value, DecodeError> {
+    let input = &mut &*bytes;
+    let value = decode_value_from(registry, type_id, input)?;
+    if input.is_empty() {
+        Ok(value)
+    } else {
+        Err(DecodeError {
+            path: "$".to_string(),
+            reason: format!("{} trailing bytes left", input.len()),
+        })
+    }
+}
+
+/// Decode a value of type `type_id` from the start of `input`, advancing it past the value.
+pub fn decode_value_from(
+    registry: &PortableRegistry,
+    type_id: u32,
+    input: &mut &[u8],
+) -> Result<Value, DecodeError> {
+    Decoder::new(registry).decode(type_id, input)
+}
+
+/// Decode a list of `fields` from the start of `input`, such as the fields of an event.
+pub fn decode_fields_from(
+    registry: &PortableRegistry,
+    fields: &[Field<PortableForm>],
+    input: &mut &[u8],
+) -> Result<Value, DecodeError> {
+    Decoder::new(registry).decode_fields(fields, input)
+}
+
+struct Decoder<'a> {
+    registry: &'a PortableRegistry,
+    /// the fields, variants and indices leading to the value being decoded
+    path: Vec<String>,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(registry: &'a PortableRegistry) -> Self {
+        Self {
+            registry,
+            path: vec![],
+        }
+    }
+
+    fn error(&self, reason: impl Into<String>) -> DecodeError {
+        let path = if self.path.is_empty() {
+            "$".to_string()
+        } else {
+            self.path.join(".")
+        };
+        DecodeError {
+            path,
+            reason: reason.into(),
+        }
+    }
+
+    fn resolve(
+        &self,
+        type_id: u32,
+    ) -> Result<&'a Type<PortableForm>, DecodeError> {
+        self.registry.resolve(type_id).ok_or_else(|| {
+            self.error(format!("type {} missing from type registry", type_id))
+        })
+    }
+
+    /// Decode a plain SCALE type, with the decoding error attached to the current path.
+    fn scale<T: Decode>(&self, input: &mut &[u8]) -> Result<T, DecodeError> {
+        T::decode(input).map_err(|e| self.error(e.to_string()))
+    }
+
+    /// Decode a value with `segment` appended to the current path.
+    fn decode_at(
+        &mut self,
+        segment: impl ToString,
+        type_id: u32,
+        input: &mut &[u8],
+    ) -> Result<Value, DecodeError> {
+        self.path.push(segment.to_string());
+        let value = self.decode(type_id, input)?;
+        self.path.pop();
+        Ok(value)
+    }
+
+    fn decode(
+        &mut self,
+        type_id: u32,
+        input: &mut &[u8],
+    ) -> Result<Value, DecodeError> {
+        let ty = self.resolve(type_id)?;
+        let name = ty.path.segments.last().map(String::as_str);
+
+        match (&ty.type_def, name) {
+            (TypeDef::Composite(_), Some("AccountId32")) => {
+                let account_id: AccountId32 = self.scale(input)?;
+                Ok(Value::String(account_id.to_ss58check()))
+            }
+            (TypeDef::Variant(variant), Some("Option")) => {
+                match self.scale::<u8>(input)? {
+                    0 => Ok(Value::Null),
+                    _ => {
+                        let some = self.variant_at(variant, 1)?;
+                        self.decode_fields(&some.fields, input)
+                    }
+                }
+            }
+            (TypeDef::Composite(composite), _) => {
+                self.decode_fields(&composite.fields, input)
+            }
+            (TypeDef::Variant(variant), _) => {
+                self.decode_variant(variant, input)
+            }
+            (TypeDef::Sequence(sequence), _) => {
+                let len = self.scale::<Compact<u32>>(input)?.0;
+                self.decode_elements(len, sequence.type_param.id, input)
+            }
+            (TypeDef::Array(array), _) => {
+                self.decode_elements(array.len, array.type_param.id, input)
+            }
+            (TypeDef::Tuple(tuple), _) => {
+                if tuple.fields.is_empty() {
+                    return Ok(Value::Null);
+                }
+                let mut values = Vec::with_capacity(tuple.fields.len());
+                for (index, field) in tuple.fields.iter().enumerate() {
+                    values.push(self.decode_at(index, field.id, input)?);
+                }
+                Ok(Value::Array(values))
+            }
+            (TypeDef::Primitive(primitive), _) => {
+                self.decode_primitive(primitive, input)
+            }
+            (TypeDef::Compact(compact), _) => {
+                self.decode_compact(compact.type_param.id, input)
+            }
+            (TypeDef::BitSequence(bits), _) => {
+                self.decode_bit_sequence(bits, input)
+            }
+        }
+    }
+
+    fn decode_fields(
+        &mut self,
+        fields: &[Field<PortableForm>],
+        input: &mut &[u8],
+    ) -> Result<Value, DecodeError> {
+        match fields {
+            [] => Ok(Value::Null),
+            // a single field wrapper, such as `AccountId32([u8; 32])`
+            [field] if field.name.is_none() => self.decode(field.ty.id, input),
+            _ if fields.iter().all(|field| field.name.is_some()) => {
+                let mut values = Map::new();
+                for field in fields {
+                    let name = field.name.clone().unwrap_or_default();
+                    let value = self.decode_at(&name, field.ty.id, input)?;
+                    values.insert(name, value);
+                }
+                Ok(Value::Object(values))
+            }
+            _ => {
+                let mut values = Vec::with_capacity(fields.len());
+                for (index, field) in fields.iter().enumerate() {
+                    values.push(self.decode_at(index, field.ty.id, input)?);
+                }
+                Ok(Value::Array(values))
+            }
+        }
+    }
+
+    fn variant_at(
+        &self,
+        variant_def: &'a TypeDefVariant<PortableForm>,
+        index: u8,
+    ) -> Result<&'a scale_info::Variant<PortableForm>, DecodeError> {
+        variant_def
+            .variants
+            .iter()
+            .find(|variant| variant.index == index)
+            .ok_or_else(|| {
+                self.error(format!("unknown variant index {}", index))
+            })
+    }
+
+    fn decode_variant(
+        &mut self,
+        variant_def: &'a TypeDefVariant<PortableForm>,
+        input: &mut &[u8],
+    ) -> Result<Value, DecodeError> {
+        let index = self.scale::<u8>(input)?;
+        let variant = self.variant_at(variant_def, index)?;
+        if variant.fields.is_empty() {
+            return Ok(Value::String(variant.name.clone()));
+        }
+        self.path.push(variant.name.clone());
+        let fields = self.decode_fields(&variant.fields, input)?;
+        self.path.pop();
+
+        let mut value = Map::new();
+        value.insert(variant.name.clone(), fields);
+        Ok(Value::Object(value))
+    }
+
+    fn decode_elements(
+        &mut self,
+        len: u32,
+        elem_id: u32,
+        input: &mut &[u8],
+    ) -> Result<Value, DecodeError> {
+        let elem = self.resolve(elem_id)?;
+        if let TypeDef::Primitive(TypeDefPrimitive::U8) = elem.type_def {
+            // don't trust the length prefix for the allocation, the bytes must
+            // all be in the input
+            if len as usize > input.len() {
+                return Err(self.error(format!(
+                    "{} bytes expected, {} left",
+                    len,
+                    input.len()
+                )));
+            }
+            let mut bytes = vec![0; len as usize];
+            input
+                .read(&mut bytes)
+                .map_err(|e| self.error(e.to_string()))?;
+            return Ok(Value::String(format!("0x{}", hex::encode(bytes))));
+        }
+        // don't trust the length prefix for the allocation
+        let mut values = Vec::with_capacity(len.min(1024) as usize);
+        for index in 0..len {
+            values.push(self.decode_at(index, elem_id, input)?);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn decode_primitive(
+        &self,
+        primitive: &TypeDefPrimitive,
+        input: &mut &[u8],
+    ) -> Result<Value, DecodeError> {
+        let value = match primitive {
+            TypeDefPrimitive::Bool => Value::from(self.scale::<bool>(input)?),
+            TypeDefPrimitive::Char => {
+                let c = char::from_u32(self.scale::<u32>(input)?)
+                    .ok_or_else(|| self.error("invalid char"))?;
+                Value::String(c.to_string())
+            }
+            TypeDefPrimitive::Str => {
+                Value::String(self.scale::<String>(input)?)
+            }
+            TypeDefPrimitive::U8 => Value::from(self.scale::<u8>(input)?),
+            TypeDefPrimitive::U16 => Value::from(self.scale::<u16>(input)?),
+            TypeDefPrimitive::U32 => Value::from(self.scale::<u32>(input)?),
+            TypeDefPrimitive::U64 => Value::from(self.scale::<u64>(input)?),
+            TypeDefPrimitive::U128 => {
+                Value::String(self.scale::<u128>(input)?.to_string())
+            }
+            TypeDefPrimitive::I8 => Value::from(self.scale::<i8>(input)?),
+            TypeDefPrimitive::I16 => Value::from(self.scale::<i16>(input)?),
+            TypeDefPrimitive::I32 => Value::from(self.scale::<i32>(input)?),
+            TypeDefPrimitive::I64 => Value::from(self.scale::<i64>(input)?),
+            TypeDefPrimitive::I128 => {
+                Value::String(self.scale::<i128>(input)?.to_string())
+            }
+            TypeDefPrimitive::U256 => {
+                Value::String(self.scale::<U256>(input)?.to_string())
+            }
+            TypeDefPrimitive::I256 => {
+                let bytes: [u8; 32] = self.scale(input)?;
+                Value::String(format!("0x{}", hex::encode(bytes)))
+            }
+        };
+        Ok(value)
+    }
+
+    fn decode_compact(
+        &self,
+        inner_id: u32,
+        input: &mut &[u8],
+    ) -> Result<Value, DecodeError> {
+        let mut ty = self.resolve(inner_id)?;
+        // compact wrappers such as `Perbill(u32)` are encoded as their inner integer
+        loop {
+            match &ty.type_def {
+                TypeDef::Composite(composite)
+                    if composite.fields.len() == 1 =>
+                {
+                    ty = self.resolve(composite.fields[0].ty.id)?;
+                }
+                TypeDef::Tuple(tuple) if tuple.fields.is_empty() => {
+                    return Ok(Value::Null);
+                }
+                TypeDef::Primitive(primitive) => {
+                    let value = match primitive {
+                        TypeDefPrimitive::U8 => {
+                            Value::from(self.scale::<Compact<u8>>(input)?.0)
+                        }
+                        TypeDefPrimitive::U16 => {
+                            Value::from(self.scale::<Compact<u16>>(input)?.0)
+                        }
+                        TypeDefPrimitive::U32 => {
+                            Value::from(self.scale::<Compact<u32>>(input)?.0)
+                        }
+                        TypeDefPrimitive::U64 => {
+                            Value::from(self.scale::<Compact<u64>>(input)?.0)
+                        }
+                        TypeDefPrimitive::U128 => Value::String(
+                            self.scale::<Compact<u128>>(input)?.0.to_string(),
+                        ),
+                        _ => {
+                            return Err(self.error(format!(
+                                "{:?} can not be compact encoded",
+                                primitive
+                            )))
+                        }
+                    };
+                    return Ok(value);
+                }
+                _ => return Err(self.error("type can not be compact encoded")),
+            }
+        }
+    }
+
+    fn decode_bit_sequence(
+        &self,
+        bits_def: &TypeDefBitSequence<PortableForm>,
+        input: &mut &[u8],
+    ) -> Result<Value, DecodeError> {
+        let store_bytes =
+            match &self.resolve(bits_def.bit_store_type.id)?.type_def {
+                TypeDef::Primitive(TypeDefPrimitive::U8) => 1,
+                TypeDef::Primitive(TypeDefPrimitive::U16) => 2,
+                TypeDef::Primitive(TypeDefPrimitive::U32) => 4,
+                TypeDef::Primitive(TypeDefPrimitive::U64) => 8,
+                _ => return Err(self.error("unsupported bit store type")),
+            };
+        let order = self.resolve(bits_def.bit_order_type.id)?;
+        let is_msb0 =
+            order.path.segments.last().map(String::as_str) == Some("Msb0");
+
+        let len = self.scale::<Compact<u32>>(input)?.0 as usize;
+        let store_bits = store_bytes * 8;
+        let words = len.div_ceil(store_bits);
+        // don't trust the length prefix for the allocation, the words must all be in
+        // the input
+        if words * store_bytes > input.len() {
+            return Err(self.error(format!(
+                "{} bytes expected, {} left",
+                words * store_bytes,
+                input.len()
+            )));
+        }
+        let mut bits = String::with_capacity(len);
+        for _ in 0..words {
+            let mut word_bytes = [0u8; 8];
+            input
+                .read(&mut word_bytes[..store_bytes])
+                .map_err(|e| self.error(e.to_string()))?;
+            let word = u64::from_le_bytes(word_bytes);
+            for i in 0..store_bits {
+                if bits.len() == len {
+                    break;
+                }
+                let shift = if is_msb0 { store_bits - 1 - i } else { i };
+                bits.push(if word & (1 << shift) != 0 { '1' } else { '0' });
+            }
+        }
+        Ok(Value::String(bits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::encode::encode_value;
+    use codec::Encode;
+    use scale_info::{meta_type, Registry, TypeInfo};
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    fn registry_of<T: TypeInfo + 'static>() -> (PortableRegistry, u32) {
+        let mut registry = Registry::new();
+        let id = registry.register_type(&meta_type::<T>()).id;
+        (registry.into(), id)
+    }
+
+    fn decode_as<T: TypeInfo + 'static>(bytes: &[u8]) -> Value {
+        let (registry, id) = registry_of::<T>();
+        decode_value(&registry, id, bytes).unwrap()
+    }
+
+    #[test]
+    fn test_decode_values() {
+        let alice = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+        let account_id = AccountId32::from_ss58check(alice).unwrap();
+        let bytes =
+            (account_id, Some(u128::MAX), Compact(7u64), None::<u8>).encode();
+        assert_eq!(
+            decode_as::<(AccountId32, Option<u128>, Compact<u64>, Option<u8>)>(
+                &bytes
+            ),
+            json!([alice, u128::MAX.to_string(), 7, null])
+        );
+
+        let map = BTreeMap::from([(1u32, vec![1u8, 2]), (2, vec![])]);
+        assert_eq!(
+            decode_as::<BTreeMap<u32, Vec<u8>>>(&map.encode()),
+            json!([[1, "0x0102"], [2, "0x"]])
+        );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let (registry, id) = registry_of::<(u32, u16)>();
+        let err = decode_value(&registry, id, &[1, 0, 0, 0, 1]).unwrap_err();
+        assert_eq!(err.path, "1");
+
+        let err = decode_value(&registry, id, &[0; 7]).unwrap_err();
+        assert_eq!(err.reason, "1 trailing bytes left");
+
+        // a length prefix of u32::MAX with a single byte of data
+        let (registry, id) = registry_of::<Vec<u8>>();
+        let mut bytes = Compact(u32::MAX).encode();
+        bytes.push(1);
+        let err = decode_value(&registry, id, &bytes).unwrap_err();
+        assert_eq!(err.reason, "4294967295 bytes expected, 1 left");
+    }
+
+    #[test]
+    fn test_decode_bit_sequence() {
+        use scale_info::{
+            form::PortableForm, Path, PortableType, Type, TypeDefBitSequence,
+            TypeDefComposite,
+        };
+        let ty = |id, segments: &[&str], type_def| PortableType {
+            id,
+            ty: Type::<PortableForm> {
+                path: Path {
+                    segments: segments.iter().map(|s| s.to_string()).collect(),
+                },
+                type_params: vec![],
+                type_def,
+                docs: vec![],
+            },
+        };
+        // a `BitVec<u8, Lsb0>`
+        let registry = PortableRegistry {
+            types: vec![
+                ty(0, &[], TypeDef::Primitive(TypeDefPrimitive::U8)),
+                ty(
+                    1,
+                    &["bitvec", "order", "Lsb0"],
+                    TypeDef::Composite(TypeDefComposite { fields: vec![] }),
+                ),
+                ty(
+                    2,
+                    &[],
+                    TypeDef::BitSequence(TypeDefBitSequence {
+                        bit_store_type: 0.into(),
+                        bit_order_type: 1.into(),
+                    }),
+                ),
+            ],
+        };
+
+        let mut bytes = Compact(10u32).encode();
+        bytes.extend([0b0000_0101, 0b0000_0010]);
+        assert_eq!(decode_value(&registry, 2, &bytes).unwrap(), "1010000001");
+
+        // a length prefix of u32::MAX bits with a single byte of data
+        let mut bytes = Compact(u32::MAX).encode();
+        bytes.push(1);
+        let err = decode_value(&registry, 2, &bytes).unwrap_err();
+        assert_eq!(err.reason, "536870912 bytes expected, 1 left");
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let (registry, id) =
+            registry_of::<(Vec<Option<u32>>, [u8; 4], Result<u8, String>)>();
+        let value = json!([[1, null, 3], "0x01020304", {"Err": "oops"}]);
+        let mut bytes = vec![];
+        encode_value(&registry, id, &value, &mut bytes).unwrap();
+        assert_eq!(decode_value(&registry, id, &bytes).unwrap(), value);
+    }
+}
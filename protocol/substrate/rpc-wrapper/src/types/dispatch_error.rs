@@ -0,0 +1,207 @@
+//! Resolve a `DispatchError` into the name and documentation of the error.
+//!
+//! This file is **not** from subxt.
+
+use crate::{
+    types::{decode, metadata::MetadataError},
+    utils::FromHexStr,
+    Error, Metadata,
+};
+use scale_info::{form::PortableForm, PortableRegistry, TypeDef, Variant};
+use serde_json::Value;
+
+/// A `DispatchError` with its pallet error, or the inner error of `Token`, `Arithmetic`
+/// and `Transactional`, resolved from the metadata.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DispatchErrorInfo {
+    /// The `DispatchError` variant, such as `Module` or `BadOrigin`.
+    pub kind: String,
+    /// The pallet which returned a `Module` error.
+    pub pallet: Option<String>,
+    /// The pallet error name, or the name of the inner error such as `FundsUnavailable`.
+    pub error: Option<String>,
+    pub docs: Vec<String>,
+}
+
+/// Resolve a `DispatchError` given either as the hex of its SCALE encoding or as json.
+///
+/// The json is the decoded form of [`crate::types::decode`], such as the `dispatch_error`
+/// field of a `System.ExtrinsicFailed` event. Variant names are matched case insensitively
+/// so that the camel case output of polkadot.js is accepted too. The `error` of a `Module`
+/// error may be either the index of the error, or the `[u8; 4]` of newer runtimes.
+pub fn resolve_dispatch_error(
+    metadata: &Metadata,
+    dispatch_error: &Value,
+) -> Result<DispatchErrorInfo, Error> {
+    let registry = metadata.type_registry()?;
+    let (type_id, variants) = dispatch_error_variants(registry)?;
+    let invalid = || Error::InvalidDispatchError(dispatch_error.clone());
+
+    let decoded;
+    let dispatch_error = match dispatch_error {
+        Value::String(hex) if hex.starts_with("0x") => {
+            let bytes = Vec::from_hex(hex)?;
+            decoded = decode::decode_value(registry, type_id, &bytes)?;
+            &decoded
+        }
+        _ => dispatch_error,
+    };
+
+    let (name, inner) = match dispatch_error {
+        Value::String(name) => (name, None),
+        Value::Object(map) if map.len() == 1 => map
+            .iter()
+            .next()
+            .map(|(name, inner)| (name, Some(inner)))
+            .unwrap(),
+        _ => return Err(invalid()),
+    };
+    let variant = find_variant(variants, name).ok_or_else(invalid)?;
+
+    if variant.name == "Module" {
+        let inner = inner.ok_or_else(invalid)?;
+        let index = module_error_index(&inner["index"]).ok_or_else(invalid)?;
+        let error = module_error_index(&inner["error"]).ok_or_else(invalid)?;
+        let error = metadata.get_error(index, error)?;
+        return Ok(DispatchErrorInfo {
+            kind: variant.name.clone(),
+            pallet: Some(error.pallet().to_string()),
+            error: Some(error.error().to_string()),
+            docs: error.description().to_vec(),
+        });
+    }
+
+    // variants wrapping another error enum, such as `Token(TokenError)`
+    let inner_variant = match (inner, variant.fields.first()) {
+        (Some(Value::String(inner_name)), Some(field)) => {
+            match registry.resolve(field.ty.id).map(|ty| &ty.type_def) {
+                Some(TypeDef::Variant(inner_def)) => {
+                    find_variant(&inner_def.variants, inner_name)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    Ok(DispatchErrorInfo {
+        kind: variant.name.clone(),
+        pallet: None,
+        error: inner_variant.map(|inner| inner.name.clone()),
+        docs: inner_variant.unwrap_or(variant).docs.clone(),
+    })
+}
+
+/// Find the `sp_runtime::DispatchError` enum in the type registry.
+fn dispatch_error_variants(
+    registry: &PortableRegistry,
+) -> Result<(u32, &[Variant<PortableForm>]), MetadataError> {
+    registry
+        .types
+        .iter()
+        .find_map(|ty| match &ty.ty.type_def {
+            TypeDef::Variant(variant)
+                if ty.ty.path.segments == ["sp_runtime", "DispatchError"] =>
+            {
+                Some((ty.id, variant.variants.as_slice()))
+            }
+            _ => None,
+        })
+        .ok_or_else(|| {
+            MetadataError::TypeNameNotFound("DispatchError".to_string())
+        })
+}
+
+fn find_variant<'a>(
+    variants: &'a [Variant<PortableForm>],
+    name: &str,
+) -> Option<&'a Variant<PortableForm>> {
+    variants
+        .iter()
+        .find(|variant| variant.name.eq_ignore_ascii_case(name))
+}
+
+/// Read a pallet or error index, given as a number, or as the first byte of an array or
+/// of a hex string.
+fn module_error_index(value: &Value) -> Option<u8> {
+    match value {
+        Value::Number(number) => number.as_u64()?.try_into().ok(),
+        Value::String(hex) => Vec::from_hex(hex).ok()?.first().copied(),
+        Value::Array(bytes) => module_error_index(bytes.first()?),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_metadata;
+    use codec::Encode;
+    use serde_json::json;
+    use sp_runtime::{DispatchError, ModuleError};
+
+    fn resolve(dispatch_error: Value) -> Result<DispatchErrorInfo, Error> {
+        resolve_dispatch_error(&test_metadata::metadata(), &dispatch_error)
+    }
+
+    fn assert_insufficient_balance(info: DispatchErrorInfo) {
+        assert_eq!(info.kind, "Module");
+        assert_eq!(info.pallet.as_deref(), Some("Balances"));
+        assert_eq!(info.error.as_deref(), Some("InsufficientBalance"));
+    }
+
+    #[test]
+    fn test_resolve_hex() {
+        let bad_origin = resolve(json!("0x02")).unwrap();
+        assert_eq!(bad_origin.kind, "BadOrigin");
+        assert_eq!(bad_origin.pallet, None);
+
+        let module = DispatchError::Module(ModuleError {
+            index: 5,
+            error: [1, 0, 0, 0],
+            message: None,
+        });
+        let hex = format!("0x{}", hex::encode(module.encode()));
+        assert_insufficient_balance(resolve(json!(hex)).unwrap());
+    }
+
+    #[test]
+    fn test_resolve_variant_names() {
+        // the camel case of polkadot.js
+        let bad_origin = resolve(json!("badOrigin")).unwrap();
+        assert_eq!(bad_origin.kind, "BadOrigin");
+        assert_eq!(bad_origin.error, None);
+
+        let token = resolve(json!({"Token": "UnknownAsset"})).unwrap();
+        assert_eq!(token.kind, "Token");
+        assert_eq!(token.error.as_deref(), Some("UnknownAsset"));
+
+        assert!(matches!(
+            resolve(json!("NoSuchError")),
+            Err(Error::InvalidDispatchError(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_module_errors() {
+        // the `[u8; 4]` error of newer runtimes, as an array or hex
+        assert_insufficient_balance(
+            resolve(json!({"Module": {"index": 5, "error": [1, 0, 0, 0]}}))
+                .unwrap(),
+        );
+        assert_insufficient_balance(
+            resolve(json!({"Module": {"index": 5, "error": "0x01000000"}}))
+                .unwrap(),
+        );
+        // the `u8` error of older runtimes
+        assert_insufficient_balance(
+            resolve(json!({"Module": {"index": 5, "error": 1}})).unwrap(),
+        );
+
+        assert!(matches!(
+            resolve(json!({"Module": {"index": 5}})),
+            Err(Error::InvalidDispatchError(_))
+        ));
+        assert!(resolve(json!({"Module": {"index": 5, "error": 9}})).is_err());
+    }
+}
@@ -0,0 +1,683 @@
+//! SCALE encode json values using the types of the metadata type registry.
+//!
+//! The accepted json for each kind of type:
+//! - composite: an object keyed by field name, or an array for unnamed fields.
+//!   A composite with a single field also accepts the value of that field directly.
+//! - variant: the variant name as a string, or an object with the variant name as its
+//!   only key and the variant fields as value. `Option` accepts `null` or the value itself.
+//! - sequence and array: an array, `u8` sequences and arrays also accept a `0x` hex string.
+//! - tuple: an array.
+//! - integers: a number, or a decimal string for values that don't fit in a json number.
+//! - bit sequence: an array of booleans or a string of `0` and `1`.
+//! - `AccountId32` and `MultiAddress` accept an SS58 address.
+//!
+//! This file is **not** from subxt.
+
+use codec::{Compact, Encode};
+use scale_info::{
+    form::PortableForm, Field, PortableRegistry, Type, TypeDef,
+    TypeDefBitSequence, TypeDefPrimitive, TypeDefVariant,
+};
+use serde_json::Value;
+use sp_core::{
+    crypto::{AccountId32, Ss58Codec},
+    U256,
+};
+
+/// Error when a json value doesn't match the type it is encoded as.
+#[derive(Debug, thiserror::Error)]
+#[error("{reason} at `{path}`")]
+pub struct EncodeError {
+    /// Location of the offending value, e.g `dest.Id`.
+    pub path: String,
+    pub reason: String,
+}
+
+/// SCALE encode `value` as the type `type_id` of the `registry`.
+pub fn encode_value(
+    registry: &PortableRegistry,
+    type_id: u32,
+    value: &Value,
+    out: &mut Vec<u8>,
+) -> Result<(), EncodeError> {
+    Encoder::new(registry).encode(type_id, value, out)
+}
+
+/// SCALE encode `value` as a list of `fields`, such as the arguments of a call.
+pub fn encode_fields(
+    registry: &PortableRegistry,
+    fields: &[Field<PortableForm>],
+    value: &Value,
+    out: &mut Vec<u8>,
+) -> Result<(), EncodeError> {
+    Encoder::new(registry).encode_fields(fields, value, out)
+}
+
+struct Encoder<'a> {
+    registry: &'a PortableRegistry,
+    /// the fields, variants and indices leading to the value being encoded
+    path: Vec<String>,
+}
+
+impl<'a> Encoder<'a> {
+    fn new(registry: &'a PortableRegistry) -> Self {
+        Self {
+            registry,
+            path: vec![],
+        }
+    }
+
+    fn error(&self, reason: impl Into<String>) -> EncodeError {
+        let path = if self.path.is_empty() {
+            "$".to_string()
+        } else {
+            self.path.join(".")
+        };
+        EncodeError {
+            path,
+            reason: reason.into(),
+        }
+    }
+
+    fn expected(&self, expected: &str, value: &Value) -> EncodeError {
+        self.error(format!("expected {}, got {}", expected, value))
+    }
+
+    fn resolve(
+        &self,
+        type_id: u32,
+    ) -> Result<&'a Type<PortableForm>, EncodeError> {
+        self.registry.resolve(type_id).ok_or_else(|| {
+            self.error(format!("type {} missing from type registry", type_id))
+        })
+    }
+
+    /// Encode `value` with `segment` appended to the current path.
+    fn encode_at(
+        &mut self,
+        segment: impl ToString,
+        type_id: u32,
+        value: &Value,
+        out: &mut Vec<u8>,
+    ) -> Result<(), EncodeError> {
+        self.path.push(segment.to_string());
+        self.encode(type_id, value, out)?;
+        self.path.pop();
+        Ok(())
+    }
+
+    fn encode(
+        &mut self,
+        type_id: u32,
+        value: &Value,
+        out: &mut Vec<u8>,
+    ) -> Result<(), EncodeError> {
+        let ty = self.resolve(type_id)?;
+        let name = ty.path.segments.last().map(String::as_str);
+
+        match (&ty.type_def, name) {
+            (TypeDef::Composite(_), Some("AccountId32"))
+                if value.is_string() =>
+            {
+                self.encode_account_id(value, out)
+            }
+            (TypeDef::Variant(variant), Some("Option")) => {
+                self.encode_option(variant, value, out)
+            }
+            (TypeDef::Variant(variant), Some("MultiAddress"))
+                if value.is_string() =>
+            {
+                self.encode_variant(variant, &single_key("Id", value), out)
+            }
+            (TypeDef::Composite(composite), _) => {
+                self.encode_fields(&composite.fields, value, out)
+            }
+            (TypeDef::Variant(variant), _) => {
+                self.encode_variant(variant, value, out)
+            }
+            (TypeDef::Sequence(sequence), _) => {
+                self.encode_sequence(sequence.type_param.id, value, out)
+            }
+            (TypeDef::Array(array), _) => {
+                self.encode_array(array.len, array.type_param.id, value, out)
+            }
+            (TypeDef::Tuple(tuple), _) => {
+                let ids: Vec<u32> = tuple.fields.iter().map(|f| f.id).collect();
+                self.encode_tuple(&ids, value, out)
+            }
+            (TypeDef::Primitive(primitive), _) => {
+                self.encode_primitive(primitive, value, out)
+            }
+            (TypeDef::Compact(compact), _) => {
+                self.encode_compact(compact.type_param.id, value, out)
+            }
+            (TypeDef::BitSequence(bits), _) => {
+                self.encode_bit_sequence(bits, value, out)
+            }
+        }
+    }
+
+    fn encode_fields(
+        &mut self,
+        fields: &[Field<PortableForm>],
+        value: &Value,
+        out: &mut Vec<u8>,
+    ) -> Result<(), EncodeError> {
+        if fields.is_empty() {
+            return match value {
+                Value::Null => Ok(()),
+                Value::Array(values) if values.is_empty() => Ok(()),
+                Value::Object(values) if values.is_empty() => Ok(()),
+                _ => Err(self.expected("no value", value)),
+            };
+        }
+        if let [field] = fields {
+            let by_name = match (&field.name, value) {
+                (Some(name), Value::Object(values)) => {
+                    values.contains_key(name) || !self.is_composite(field.ty.id)
+                }
+                _ => false,
+            };
+            if !by_name {
+                // a single field wrapper, such as `AccountId32([u8; 32])`
+                return self.encode(field.ty.id, value, out);
+            }
+        }
+        let is_named = fields.iter().all(|field| field.name.is_some());
+
+        match value {
+            Value::Object(values) if is_named => {
+                for field in fields {
+                    let name = field.name.as_deref().unwrap_or_default();
+                    match values.get(name) {
+                        Some(field_value) => {
+                            self.encode_at(name, field.ty.id, field_value, out)?
+                        }
+                        None if self.is_option(field.ty.id) => out.push(0),
+                        None => {
+                            return Err(
+                                self.error(format!("missing field `{}`", name))
+                            )
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Value::Array(values) if values.len() == fields.len() => {
+                for (index, (field, field_value)) in
+                    fields.iter().zip(values).enumerate()
+                {
+                    let segment =
+                        field.name.clone().unwrap_or_else(|| index.to_string());
+                    self.encode_at(segment, field.ty.id, field_value, out)?;
+                }
+                Ok(())
+            }
+            _ if is_named => {
+                let names = fields
+                    .iter()
+                    .map(|field| field.name.as_deref().unwrap_or_default())
+                    .collect::<Vec<_>>();
+                Err(self.expected(
+                    &format!("an object with fields {}", names.join(", ")),
+                    value,
+                ))
+            }
+            _ => Err(self.expected(
+                &format!("an array of {} values", fields.len()),
+                value,
+            )),
+        }
+    }
+
+    fn encode_variant(
+        &mut self,
+        variant_def: &TypeDefVariant<PortableForm>,
+        value: &Value,
+        out: &mut Vec<u8>,
+    ) -> Result<(), EncodeError> {
+        let (name, fields_value) = match value {
+            Value::String(name) => (name.as_str(), &Value::Null),
+            Value::Object(values) if values.len() == 1 => {
+                let (name, fields_value) =
+                    values.iter().next().expect("must have one entry");
+                (name.as_str(), fields_value)
+            }
+            _ => {
+                return Err(self.expected(
+                    &format!(
+                        "a variant name or an object with a single variant key, one of: {}",
+                        variant_names(variant_def)
+                    ),
+                    value,
+                ))
+            }
+        };
+        let variant = variant_def
+            .variants
+            .iter()
+            .find(|variant| variant.name == name)
+            .ok_or_else(|| {
+                self.error(format!(
+                    "unknown variant `{}`, expected one of: {}",
+                    name,
+                    variant_names(variant_def)
+                ))
+            })?;
+
+        out.push(variant.index);
+        self.path.push(name.to_string());
+        self.encode_fields(&variant.fields, fields_value, out)?;
+        self.path.pop();
+        Ok(())
+    }
+
+    fn encode_option(
+        &mut self,
+        variant_def: &TypeDefVariant<PortableForm>,
+        value: &Value,
+        out: &mut Vec<u8>,
+    ) -> Result<(), EncodeError> {
+        match value {
+            Value::Null => {
+                out.push(0);
+                Ok(())
+            }
+            Value::Object(values)
+                if values.len() == 1
+                    && (values.contains_key("Some")
+                        || values.contains_key("None")) =>
+            {
+                self.encode_variant(variant_def, value, out)
+            }
+            _ => self.encode_variant(
+                variant_def,
+                &single_key("Some", value),
+                out,
+            ),
+        }
+    }
+
+    fn is_option(&self, type_id: u32) -> bool {
+        self.registry.resolve(type_id).is_some_and(|ty| {
+            matches!(ty.type_def, TypeDef::Variant(_))
+                && ty.path.segments == ["Option"]
+        })
+    }
+
+    fn encode_account_id(
+        &self,
+        value: &Value,
+        out: &mut Vec<u8>,
+    ) -> Result<(), EncodeError> {
+        let address = value.as_str().unwrap_or_default();
+        let account_id = if address.starts_with("0x") {
+            let bytes = self.hex_bytes(address, Some(32))?;
+            AccountId32::new(bytes.try_into().expect("must be 32 bytes"))
+        } else {
+            AccountId32::from_ss58check(address).map_err(|e| {
+                self.error(format!("invalid ss58 address: {:?}", e))
+            })?
+        };
+        account_id.encode_to(out);
+        Ok(())
+    }
+
+    fn encode_sequence(
+        &mut self,
+        elem_id: u32,
+        value: &Value,
+        out: &mut Vec<u8>,
+    ) -> Result<(), EncodeError> {
+        match value {
+            Value::String(s) if self.is_u8(elem_id) => {
+                let bytes = if s.starts_with("0x") {
+                    self.hex_bytes(s, None)?
+                } else {
+                    s.as_bytes().to_vec()
+                };
+                bytes.encode_to(out);
+                Ok(())
+            }
+            Value::Array(values) => {
+                Compact(values.len() as u32).encode_to(out);
+                for (index, elem) in values.iter().enumerate() {
+                    self.encode_at(index, elem_id, elem, out)?;
+                }
+                Ok(())
+            }
+            _ => Err(self.expected("an array", value)),
+        }
+    }
+
+    fn encode_array(
+        &mut self,
+        len: u32,
+        elem_id: u32,
+        value: &Value,
+        out: &mut Vec<u8>,
+    ) -> Result<(), EncodeError> {
+        match value {
+            Value::String(s) if self.is_u8(elem_id) => {
+                out.extend(self.hex_bytes(s, Some(len as usize))?);
+                Ok(())
+            }
+            Value::Array(values) if values.len() == len as usize => {
+                for (index, elem) in values.iter().enumerate() {
+                    self.encode_at(index, elem_id, elem, out)?;
+                }
+                Ok(())
+            }
+            _ => {
+                Err(self
+                    .expected(&format!("an array of {} values", len), value))
+            }
+        }
+    }
+
+    fn encode_tuple(
+        &mut self,
+        ids: &[u32],
+        value: &Value,
+        out: &mut Vec<u8>,
+    ) -> Result<(), EncodeError> {
+        match value {
+            Value::Null if ids.is_empty() => Ok(()),
+            Value::Array(values) if values.len() == ids.len() => {
+                for (index, (id, elem)) in ids.iter().zip(values).enumerate() {
+                    self.encode_at(index, *id, elem, out)?;
+                }
+                Ok(())
+            }
+            _ if ids.len() == 1 => self.encode(ids[0], value, out),
+            _ => Err(self
+                .expected(&format!("an array of {} values", ids.len()), value)),
+        }
+    }
+
+    fn encode_primitive(
+        &self,
+        primitive: &TypeDefPrimitive,
+        value: &Value,
+        out: &mut Vec<u8>,
+    ) -> Result<(), EncodeError> {
+        match primitive {
+            TypeDefPrimitive::Bool => value
+                .as_bool()
+                .ok_or_else(|| self.expected("a boolean", value))?
+                .encode_to(out),
+            TypeDefPrimitive::Char => {
+                let mut chars = value.as_str().unwrap_or_default().chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => (c as u32).encode_to(out),
+                    _ => return Err(self.expected("a single character", value)),
+                }
+            }
+            TypeDefPrimitive::Str => value
+                .as_str()
+                .ok_or_else(|| self.expected("a string", value))?
+                .encode_to(out),
+            TypeDefPrimitive::U8 => self.uint::<u8>(value)?.encode_to(out),
+            TypeDefPrimitive::U16 => self.uint::<u16>(value)?.encode_to(out),
+            TypeDefPrimitive::U32 => self.uint::<u32>(value)?.encode_to(out),
+            TypeDefPrimitive::U64 => self.uint::<u64>(value)?.encode_to(out),
+            TypeDefPrimitive::U128 => self.uint::<u128>(value)?.encode_to(out),
+            TypeDefPrimitive::I8 => self.int::<i8>(value)?.encode_to(out),
+            TypeDefPrimitive::I16 => self.int::<i16>(value)?.encode_to(out),
+            TypeDefPrimitive::I32 => self.int::<i32>(value)?.encode_to(out),
+            TypeDefPrimitive::I64 => self.int::<i64>(value)?.encode_to(out),
+            TypeDefPrimitive::I128 => self.int::<i128>(value)?.encode_to(out),
+            TypeDefPrimitive::U256 => {
+                let number = match value {
+                    Value::Number(n) => n.as_u64().map(U256::from),
+                    Value::String(s) => U256::from_dec_str(s).ok(),
+                    _ => None,
+                };
+                number
+                    .ok_or_else(|| self.expected("an unsigned integer", value))?
+                    .encode_to(out)
+            }
+            TypeDefPrimitive::I256 => {
+                return Err(self.error("encoding i256 is not supported"))
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_compact(
+        &self,
+        inner_id: u32,
+        value: &Value,
+        out: &mut Vec<u8>,
+    ) -> Result<(), EncodeError> {
+        let mut ty = self.resolve(inner_id)?;
+        // compact wrappers such as `Perbill(u32)` are encoded as their inner integer
+        loop {
+            match &ty.type_def {
+                TypeDef::Composite(composite)
+                    if composite.fields.len() == 1 =>
+                {
+                    ty = self.resolve(composite.fields[0].ty.id)?;
+                }
+                TypeDef::Tuple(tuple) if tuple.fields.is_empty() => {
+                    return Ok(());
+                }
+                TypeDef::Primitive(primitive) => {
+                    match primitive {
+                        TypeDefPrimitive::U8 => {
+                            Compact(self.uint::<u8>(value)?).encode_to(out)
+                        }
+                        TypeDefPrimitive::U16 => {
+                            Compact(self.uint::<u16>(value)?).encode_to(out)
+                        }
+                        TypeDefPrimitive::U32 => {
+                            Compact(self.uint::<u32>(value)?).encode_to(out)
+                        }
+                        TypeDefPrimitive::U64 => {
+                            Compact(self.uint::<u64>(value)?).encode_to(out)
+                        }
+                        TypeDefPrimitive::U128 => {
+                            Compact(self.uint::<u128>(value)?).encode_to(out)
+                        }
+                        _ => {
+                            return Err(self.error(format!(
+                                "{:?} can not be compact encoded",
+                                primitive
+                            )))
+                        }
+                    }
+                    return Ok(());
+                }
+                _ => {
+                    return Err(self
+                        .error("type can not be compact encoded".to_string()))
+                }
+            }
+        }
+    }
+
+    fn encode_bit_sequence(
+        &self,
+        bits_def: &TypeDefBitSequence<PortableForm>,
+        value: &Value,
+        out: &mut Vec<u8>,
+    ) -> Result<(), EncodeError> {
+        let bits = match value {
+            Value::Array(values) => values
+                .iter()
+                .map(|bit| bit.as_bool())
+                .collect::<Option<Vec<bool>>>(),
+            Value::String(s) => s
+                .chars()
+                .map(|c| match c {
+                    '0' => Some(false),
+                    '1' => Some(true),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            self.expected("an array of booleans or a string of 0 and 1", value)
+        })?;
+
+        let store_bytes =
+            match &self.resolve(bits_def.bit_store_type.id)?.type_def {
+                TypeDef::Primitive(TypeDefPrimitive::U8) => 1,
+                TypeDef::Primitive(TypeDefPrimitive::U16) => 2,
+                TypeDef::Primitive(TypeDefPrimitive::U32) => 4,
+                TypeDef::Primitive(TypeDefPrimitive::U64) => 8,
+                _ => return Err(self.error("unsupported bit store type")),
+            };
+        let order = self.resolve(bits_def.bit_order_type.id)?;
+        let is_msb0 =
+            order.path.segments.last().map(String::as_str) == Some("Msb0");
+
+        Compact(bits.len() as u32).encode_to(out);
+        let store_bits = store_bytes * 8;
+        for chunk in bits.chunks(store_bits) {
+            let mut word: u64 = 0;
+            for (i, bit) in chunk.iter().enumerate() {
+                if *bit {
+                    let shift = if is_msb0 { store_bits - 1 - i } else { i };
+                    word |= 1 << shift;
+                }
+            }
+            out.extend(&word.to_le_bytes()[..store_bytes]);
+        }
+        Ok(())
+    }
+
+    fn is_composite(&self, type_id: u32) -> bool {
+        self.registry
+            .resolve(type_id)
+            .is_some_and(|ty| matches!(ty.type_def, TypeDef::Composite(_)))
+    }
+
+    fn is_u8(&self, type_id: u32) -> bool {
+        self.registry.resolve(type_id).is_some_and(|ty| {
+            matches!(ty.type_def, TypeDef::Primitive(TypeDefPrimitive::U8))
+        })
+    }
+
+    fn hex_bytes(
+        &self,
+        hex_str: &str,
+        len: Option<usize>,
+    ) -> Result<Vec<u8>, EncodeError> {
+        let bytes = hex::decode(hex_str.trim_start_matches("0x"))
+            .map_err(|e| self.error(format!("invalid hex string: {}", e)))?;
+        match len {
+            Some(len) if bytes.len() != len => Err(self.error(format!(
+                "expected {} bytes, got {}",
+                len,
+                bytes.len()
+            ))),
+            _ => Ok(bytes),
+        }
+    }
+
+    fn uint<T: TryFrom<u128>>(&self, value: &Value) -> Result<T, EncodeError> {
+        let number = match value {
+            Value::Number(n) => n.as_u64().map(u128::from),
+            Value::String(s) => s.parse::<u128>().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| self.expected("an unsigned integer", value))?;
+        T::try_from(number)
+            .map_err(|_| self.error(format!("{} is out of range", number)))
+    }
+
+    fn int<T: TryFrom<i128>>(&self, value: &Value) -> Result<T, EncodeError> {
+        let number = match value {
+            Value::Number(n) => n.as_i64().map(i128::from),
+            Value::String(s) => s.parse::<i128>().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| self.expected("an integer", value))?;
+        T::try_from(number)
+            .map_err(|_| self.error(format!("{} is out of range", number)))
+    }
+}
+
+fn single_key(key: &str, value: &Value) -> Value {
+    let mut map = serde_json::Map::new();
+    map.insert(key.to_string(), value.clone());
+    Value::Object(map)
+}
+
+fn variant_names(variant_def: &TypeDefVariant<PortableForm>) -> String {
+    variant_def
+        .variants
+        .iter()
+        .map(|variant| variant.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale_info::{meta_type, Registry, TypeInfo};
+    use serde_json::json;
+    use sp_runtime::MultiAddress;
+
+    const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+    fn encode_json<T: TypeInfo + 'static>(
+        value: Value,
+    ) -> Result<Vec<u8>, EncodeError> {
+        let mut registry = Registry::new();
+        let id = registry.register_type(&meta_type::<T>()).id;
+        let registry: PortableRegistry = registry.into();
+        let mut out = vec![];
+        encode_value(&registry, id, &value, &mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_encode_transfer_args() {
+        let bob = AccountId32::from_ss58check(BOB).unwrap();
+        let encoded = encode_json::<(
+            MultiAddress<AccountId32, u32>,
+            Compact<u128>,
+        )>(json!([BOB, "1000000000000"]))
+        .unwrap();
+        let expected = (
+            MultiAddress::<AccountId32, u32>::Id(bob),
+            Compact(1_000_000_000_000u128),
+        )
+            .encode();
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn test_encode_collections() {
+        assert_eq!(
+            encode_json::<Vec<Option<u32>>>(json!([1, null])).unwrap(),
+            vec![Some(1u32), None].encode()
+        );
+        assert_eq!(
+            encode_json::<Vec<u8>>(json!("0x0102")).unwrap(),
+            vec![1u8, 2].encode()
+        );
+        assert_eq!(
+            encode_json::<[u8; 2]>(json!([3, 4])).unwrap(),
+            [3u8, 4].encode()
+        );
+    }
+
+    #[test]
+    fn test_encode_error_path() {
+        let err =
+            encode_json::<(u32, Vec<u8>)>(json!([1, [2, 300]])).unwrap_err();
+        assert_eq!(err.path, "1.1");
+        assert_eq!(err.reason, "300 is out of range");
+
+        let err =
+            encode_json::<MultiAddress<AccountId32, u32>>(json!({"Foo": 1}))
+                .unwrap_err();
+        assert_eq!(err.path, "$");
+        assert!(err.reason.starts_with("unknown variant `Foo`"));
+    }
+}
@@ -0,0 +1,136 @@
+//! Decode the event records held in the `System.Events` storage.
+//!
+//! This file is **not** from subxt.
+
+use crate::{types::decode, Error, Metadata};
+use codec::{Compact, Decode};
+use sp_core::H256;
+
+/// The phase of the block execution in which an event was emitted.
+#[derive(Clone, Debug, PartialEq, Eq, Decode)]
+pub enum Phase {
+    /// Applying the extrinsic at this index of the block.
+    ApplyExtrinsic(u32),
+    /// Finalizing the block.
+    Finalization,
+    /// Initializing the block.
+    Initialization,
+}
+
+/// An event of a block, with its fields decoded into json.
+#[derive(Clone, Debug)]
+pub struct EventRecord {
+    pub phase: Phase,
+    pub pallet: String,
+    pub event: String,
+    pub fields: serde_json::Value,
+    pub topics: Vec<H256>,
+}
+
+/// Decode the SCALE encoded `Vec<EventRecord>` of `System.Events`.
+///
+/// The events are looked up in `metadata`, so it must be the metadata of the runtime
+/// which emitted them.
+pub fn decode_event_records(
+    metadata: &Metadata,
+    bytes: &[u8],
+) -> Result<Vec<EventRecord>, Error> {
+    let registry = metadata.type_registry()?;
+    let input = &mut &*bytes;
+    let len = Compact::<u32>::decode(input)?.0;
+
+    // don't trust the length prefix for the allocation
+    let mut records = Vec::with_capacity(len.min(1024) as usize);
+    for _ in 0..len {
+        let phase = Phase::decode(input)?;
+        let [pallet_index, event_index] = <[u8; 2]>::decode(input)?;
+        let event = metadata.get_event(pallet_index, event_index)?;
+        let fields = decode::decode_fields_from(
+            registry,
+            &event.variant().fields,
+            input,
+        )?;
+        let topics = Vec::<H256>::decode(input)?;
+
+        records.push(EventRecord {
+            phase,
+            pallet: event.pallet().to_string(),
+            event: event.event().to_string(),
+            fields,
+            topics,
+        });
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_metadata::{
+        self, BalancesEvent, RuntimeEvent, SystemEvent,
+    };
+    use codec::Encode;
+    use serde_json::json;
+    use sp_core::crypto::{AccountId32, Ss58Codec};
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+    #[test]
+    fn test_decode_event_records() {
+        let record = |phase, event, topics| test_metadata::EventRecord {
+            phase,
+            event,
+            topics,
+        };
+        let transfer = RuntimeEvent::Balances(BalancesEvent::Transfer {
+            from: AccountId32::from_ss58check(ALICE).unwrap(),
+            to: AccountId32::from_ss58check(BOB).unwrap(),
+            amount: 10,
+        });
+        let topic = H256::repeat_byte(9);
+        let bytes = vec![
+            record(
+                test_metadata::Phase::Initialization,
+                RuntimeEvent::System(SystemEvent::CodeUpdated),
+                vec![],
+            ),
+            record(
+                test_metadata::Phase::ApplyExtrinsic(1),
+                transfer,
+                vec![topic],
+            ),
+            record(
+                test_metadata::Phase::Finalization,
+                RuntimeEvent::System(SystemEvent::ExtrinsicSuccess),
+                vec![],
+            ),
+        ]
+        .encode();
+
+        let records =
+            decode_event_records(&test_metadata::metadata(), &bytes).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].phase, Phase::Initialization);
+        assert_eq!(records[0].event, "CodeUpdated");
+        assert_eq!(records[1].phase, Phase::ApplyExtrinsic(1));
+        assert_eq!(records[1].pallet, "Balances");
+        assert_eq!(records[1].event, "Transfer");
+        assert_eq!(
+            records[1].fields,
+            json!({"from": ALICE, "to": BOB, "amount": "10"})
+        );
+        assert_eq!(records[1].topics, [topic]);
+        assert_eq!(records[2].phase, Phase::Finalization);
+        assert_eq!(records[2].pallet, "System");
+        assert_eq!(records[2].event, "ExtrinsicSuccess");
+        assert!(records[2].topics.is_empty());
+
+        // the records must all be there
+        let err = decode_event_records(
+            &test_metadata::metadata(),
+            &bytes[..bytes.len() - 1],
+        );
+        assert!(err.is_err());
+    }
+}
@@ -1,14 +1,114 @@
 //! Primitives for substrate extrinsics.
 use crate::{
-    signer_provider_module, types::extrinsic_params::ExtrinsicParams,
-    utils::Encoded, SignerProviderModule, SignerProviderSignerPayloadRaw,
+    signer_provider_module,
+    types::{decode, encode, extrinsic_params::ExtrinsicParams},
+    utils::Encoded,
+    Error, Metadata, SignerProviderModule, SignerProviderSignerPayloadRaw,
 };
-use codec::{Compact, Encode};
+use codec::{Compact, Decode, Encode};
+use serde_json::{json, Map, Value};
 use sp_core::{
     crypto::{AccountId32, Ss58Codec},
-    sr25519::Signature,
+    H256,
 };
-use sp_runtime::{MultiAddress, MultiSignature};
+use sp_runtime::{generic::Era, MultiAddress, MultiSignature};
+use std::convert::TryInto;
+
+/// Bit of the version byte set for signed extrinsics.
+const SIGNED_BIT: u8 = 0b1000_0000;
+/// The transaction protocol version written and read by this crate.
+const EXTRINSIC_VERSION: u8 = 4;
+
+/// The signature scheme of the key of a signer provider account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureScheme {
+    Sr25519,
+    Ed25519,
+    Ecdsa,
+    /// Ecdsa over keccak hashes, with 20 bytes accounts such as on Moonbeam.
+    Ethereum,
+}
+
+impl SignatureScheme {
+    /// The scheme of the account `type` of the signer provider, sr25519 when it has none
+    /// as in polkadot.js.
+    pub fn from_account_type(
+        account_type: Option<&str>,
+    ) -> Result<Self, Error> {
+        match account_type {
+            None | Some("sr25519") => Ok(Self::Sr25519),
+            Some("ed25519") => Ok(Self::Ed25519),
+            Some("ecdsa") => Ok(Self::Ecdsa),
+            Some("ethereum") => Ok(Self::Ethereum),
+            Some(other) => Err(Error::UnsupportedSignerType(other.to_string())),
+        }
+    }
+
+    /// The scheme of the signer provider account with `address`.
+    pub fn of_signer(address: &str) -> Result<Self, Error> {
+        let accounts = SignerProviderModule::get_accounts(
+            &signer_provider_module::ArgsGetAccounts {},
+        )
+        .map_err(Error::SignerProviderError)?;
+        let account = accounts
+            .iter()
+            .find(|account| same_address(&account.address, address))
+            .ok_or_else(|| Error::SignerNotFound(address.to_string()))?;
+        Self::from_account_type(account._type.as_deref())
+    }
+
+    /// The name of the `MultiSignature` variant of the scheme.
+    fn multi_signature_variant(self) -> &'static str {
+        match self {
+            Self::Sr25519 => "Sr25519",
+            Self::Ed25519 => "Ed25519",
+            Self::Ecdsa | Self::Ethereum => "Ecdsa",
+        }
+    }
+
+    /// The length of the signatures of the scheme.
+    fn signature_len(self) -> usize {
+        match self {
+            Self::Sr25519 | Self::Ed25519 => 64,
+            Self::Ecdsa | Self::Ethereum => 65,
+        }
+    }
+
+    /// Drop the `MultiSignature` variant index polkadot.js puts before the signatures of
+    /// `signPayload`.
+    fn without_type_prefix(self, mut signature: Vec<u8>) -> Vec<u8> {
+        if signature.len() == self.signature_len() + 1 {
+            signature.remove(0);
+        }
+        signature
+    }
+
+    /// Wrap the `signature` bytes into a `MultiSignature`, `None` if they are not a
+    /// signature of the scheme.
+    fn multi_signature(self, signature: &[u8]) -> Option<MultiSignature> {
+        Some(match self {
+            Self::Sr25519 => {
+                MultiSignature::Sr25519(signature.try_into().ok()?)
+            }
+            Self::Ed25519 => {
+                MultiSignature::Ed25519(signature.try_into().ok()?)
+            }
+            Self::Ecdsa | Self::Ethereum => {
+                MultiSignature::Ecdsa(signature.try_into().ok()?)
+            }
+        })
+    }
+}
+
+/// Whether the addresses are of the same account. SS58 addresses are base58 so case
+/// sensitive, only the hex of `0x` AccountId20 addresses may differ in case, as in the
+/// checksummed addresses of Ethereum wallets.
+fn same_address(a: &str, b: &str) -> bool {
+    match (a.strip_prefix("0x"), b.strip_prefix("0x")) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        _ => a == b,
+    }
+}
 
 /// Builder of custom extrinsics.
 pub struct ExtrinsicBuilder {
@@ -22,52 +122,97 @@ impl ExtrinsicBuilder {
         Self { call_data, params }
     }
 
-    // Construct signature.
+    // Construct signature, with `signPayload` so wallets can show the decoded
+    // transaction, or with `signRaw` over the encoded payload if `sign_raw` or if the
+    // signed extensions have data `signPayload` can't carry.
     //
     // This is compatible with the Encode impl for SignedPayload (which is this payload of bytes that we'd like)
     // to sign. See: https://github.com/paritytech/substrate/blob/9a6d706d8db00abb6ba183839ec98ecd9924b1f8/primitives/runtime/src/generic/unchecked_extrinsic.rs#L215)
-    fn signature(&self, signer: &str) -> MultiSignature {
-        let mut bytes = Vec::new();
-        self.call_data.encode_to(&mut bytes);
-        self.params.encode_extra_to(&mut bytes);
-        self.params.encode_additional_to(&mut bytes);
-
-        let data = if bytes.len() > 256 {
-            sp_core::blake2_256(&bytes).to_vec()
+    fn signature(
+        &self,
+        signer: &str,
+        scheme: SignatureScheme,
+        sign_raw: bool,
+    ) -> Result<Vec<u8>, Error> {
+        let payload = if sign_raw {
+            None
         } else {
-            bytes
+            self.params.signer_payload(
+                signer,
+                &self.call_data,
+                EXTRINSIC_VERSION,
+            )
         };
+        let result = match payload {
+            Some(payload) => SignerProviderModule::sign_payload(
+                &signer_provider_module::ArgsSignPayload { payload },
+            ),
+            None => {
+                let mut bytes = Vec::new();
+                self.call_data.encode_to(&mut bytes);
+                self.params.encode_extra_to(&mut bytes);
+                self.params.encode_additional_to(&mut bytes);
 
-        let sig = SignerProviderModule::sign_raw(
-            &signer_provider_module::ArgsSignRaw {
-                payload: SignerProviderSignerPayloadRaw {
-                    _type: "bytes".into(),
-                    address: signer.into(),
-                    data: hex::encode(data),
-                },
-            },
-        )
-        .expect("Failed to sign extrinsic.")
-        .signature;
+                let data = if bytes.len() > 256 {
+                    sp_core::blake2_256(&bytes).to_vec()
+                } else {
+                    bytes
+                };
 
-        let mut sr25519_sig = [0; 64];
-        sr25519_sig.copy_from_slice(
-            &hex::decode(sig.trim_start_matches("0x"))
-                .expect("Invalid signature"),
-        );
-        MultiSignature::Sr25519(Signature(sr25519_sig))
+                SignerProviderModule::sign_raw(
+                    &signer_provider_module::ArgsSignRaw {
+                        payload: SignerProviderSignerPayloadRaw {
+                            _type: "bytes".into(),
+                            address: signer.into(),
+                            data: hex::encode(data),
+                        },
+                    },
+                )
+            }
+        };
+        let sig = result.map_err(Error::SignerProviderError)?.signature;
+
+        Ok(scheme
+            .without_type_prefix(hex::decode(sig.trim_start_matches("0x"))?))
     }
 
-    /// Encode extrinsic (protocol version 4).
-    pub fn build(&self, acc: AccountId32) -> Vec<u8> {
-        let address = acc.to_ss58check();
+    /// Encode extrinsic (protocol version 4), signed by the account with `address` whose key
+    /// is of the `scheme`, through `signRaw` if `sign_raw` and else `signPayload` when the
+    /// payload can carry the signed extensions.
+    ///
+    /// The address and signature are encoded with the types of the chain in `metadata`,
+    /// such as `MultiAddress` and `MultiSignature`, or `AccountId20` and
+    /// `EthereumSignature` on Ethereum compatible chains.
+    pub fn build(
+        &self,
+        metadata: &Metadata,
+        address: &str,
+        scheme: SignatureScheme,
+        sign_raw: bool,
+    ) -> Result<Vec<u8>, Error> {
+        let signature = self.signature(address, scheme, sign_raw)?;
+        self.encode_signed(metadata, address, scheme, &signature)
+    }
+
+    /// Encode the extrinsic with the `signature` of the account with `address`.
+    fn encode_signed(
+        &self,
+        metadata: &Metadata,
+        address: &str,
+        scheme: SignatureScheme,
+        signature: &[u8],
+    ) -> Result<Vec<u8>, Error> {
         let mut encoded_inner = Vec::new();
         // "is signed" + transaction protocol version (4)
-        (0b10000000 + 4u8).encode_to(&mut encoded_inner);
-        // from address for signature
-        MultiAddress::<AccountId32, u32>::Id(acc).encode_to(&mut encoded_inner);
-        // the signature bytes
-        self.signature(&address).encode_to(&mut encoded_inner);
+        (SIGNED_BIT + EXTRINSIC_VERSION).encode_to(&mut encoded_inner);
+        // from address, and the signature bytes
+        encode_signer(
+            metadata,
+            address,
+            scheme,
+            signature,
+            &mut encoded_inner,
+        )?;
         // attach custom extra params
         self.params.encode_extra_to(&mut encoded_inner);
         // and now, call data
@@ -80,6 +225,373 @@ impl ExtrinsicBuilder {
         let mut encoded = Vec::new();
         len.encode_to(&mut encoded);
         encoded.extend(encoded_inner);
-        encoded
+        Ok(encoded)
+    }
+}
+
+/// SCALE encode the `address` of the signer and its `signature` with the address and
+/// signature types of the extrinsic metadata.
+///
+/// Legacy metadata has no types, its chains are assumed to take a `MultiAddress` with an
+/// `AccountId32` and a `MultiSignature`.
+fn encode_signer(
+    metadata: &Metadata,
+    address: &str,
+    scheme: SignatureScheme,
+    signature: &[u8],
+    out: &mut Vec<u8>,
+) -> Result<(), Error> {
+    let extrinsic = &metadata.metadata.extrinsic;
+    let invalid_signature = || Error::InvalidSignature(hex::encode(signature));
+    if !metadata.has_type_registry() {
+        let account = AccountId32::from_ss58check(address)?;
+        MultiAddress::<AccountId32, u32>::Id(account).encode_to(out);
+        scheme
+            .multi_signature(signature)
+            .ok_or_else(invalid_signature)?
+            .encode_to(out);
+        return Ok(());
+    }
+
+    let registry = metadata.type_registry()?;
+    encode::encode_value(
+        registry,
+        extrinsic.address_ty.id,
+        &Value::String(address.to_string()),
+        out,
+    )?;
+    let signature_hex = Value::String(format!("0x{}", hex::encode(signature)));
+    let signature_ty = registry.resolve(extrinsic.signature_ty.id);
+    let signature_value = match signature_ty
+        .and_then(|ty| ty.path.segments.last())
+        .map(String::as_str)
+    {
+        Some("MultiSignature") => {
+            json!({ scheme.multi_signature_variant(): signature_hex })
+        }
+        // a single scheme signature, such as `EthereumSignature`
+        _ => signature_hex,
+    };
+    encode::encode_value(
+        registry,
+        extrinsic.signature_ty.id,
+        &signature_value,
+        out,
+    )
+    .map_err(|_| invalid_signature())
+}
+
+/// An extrinsic of a block, decoded using the metadata.
+#[derive(Clone, Debug)]
+pub struct DecodedExtrinsic {
+    /// The blake2_256 hash of the encoded extrinsic.
+    pub hash: H256,
+    /// The signature, when the extrinsic is signed.
+    pub signature: Option<ExtrinsicSignature>,
+    pub pallet: String,
+    pub call: String,
+    /// The call arguments, decoded like [`crate::types::decode`].
+    pub args: Value,
+}
+
+/// The signer, signature and signed extensions of a signed extrinsic.
+#[derive(Clone, Debug)]
+pub struct ExtrinsicSignature {
+    /// The SS58 address of the signer, or the hex of non `AccountId32` addresses.
+    pub signer: String,
+    /// The signature variant, such as `Sr25519`.
+    pub signature_type: String,
+    /// The signature bytes as hex.
+    pub signature: Value,
+    pub era: Era,
+    pub nonce: Option<u32>,
+    pub tip: Option<Value>,
+    /// The values of all the signed extensions, by identifier.
+    pub extensions: Map<String, Value>,
+}
+
+/// Decode an extrinsic with the layout written by [`ExtrinsicBuilder::build`].
+///
+/// The address, signature, signed extensions and call types are taken from `metadata`.
+pub fn decode_extrinsic(
+    metadata: &Metadata,
+    bytes: &[u8],
+) -> Result<DecodedExtrinsic, Error> {
+    let registry = metadata.type_registry()?;
+    let extrinsic = &metadata.metadata.extrinsic;
+    let input = &mut &*bytes;
+
+    let _len = Compact::<u32>::decode(input)?;
+    let version = u8::decode(input)?;
+    if version & !SIGNED_BIT != EXTRINSIC_VERSION {
+        return Err(codec::Error::from("Unsupported extrinsic version").into());
+    }
+
+    let signature = if version & SIGNED_BIT != 0 {
+        let address = decode::decode_value_from(
+            registry,
+            extrinsic.address_ty.id,
+            input,
+        )?;
+        let signature = decode::decode_value_from(
+            registry,
+            extrinsic.signature_ty.id,
+            input,
+        )?;
+        let (signature_type, signature) = match signature {
+            Value::Object(map) if map.len() == 1 => {
+                map.into_iter().next().unwrap()
+            }
+            signature => {
+                let ty = registry.resolve(extrinsic.signature_ty.id);
+                let name = ty.and_then(|ty| ty.path.segments.last());
+                (name.cloned().unwrap_or_default(), signature)
+            }
+        };
+
+        let mut era = Era::Immortal;
+        let mut extensions = Map::new();
+        for extension in &extrinsic.signed_extensions {
+            if extension.identifier == "CheckMortality" {
+                era = Era::decode(&mut &**input)?;
+            }
+            let value =
+                decode::decode_value_from(registry, extension.ty.id, input)?;
+            extensions.insert(extension.identifier.clone(), value);
+        }
+        let nonce = extensions
+            .get("CheckNonce")
+            .and_then(Value::as_u64)
+            .map(|nonce| nonce as u32);
+        let tip = match (
+            extensions.get("ChargeTransactionPayment"),
+            extensions.get("ChargeAssetTxPayment"),
+        ) {
+            (Some(tip), _) => Some(tip.clone()),
+            (None, Some(payment)) => Some(payment["tip"].clone()),
+            (None, None) => None,
+        };
+
+        Some(ExtrinsicSignature {
+            signer: address_to_string(address),
+            signature_type,
+            signature,
+            era,
+            nonce,
+            tip,
+            extensions,
+        })
+    } else {
+        None
+    };
+
+    let [pallet_index, call_index] = <[u8; 2]>::decode(input)?;
+    let (pallet, call) =
+        metadata.call_variant_by_index(pallet_index, call_index)?;
+    let args = decode::decode_fields_from(registry, &call.fields, input)?;
+
+    Ok(DecodedExtrinsic {
+        hash: H256(sp_core::blake2_256(bytes)),
+        signature,
+        pallet: pallet.to_string(),
+        call: call.name.clone(),
+        args,
+    })
+}
+
+/// Unwrap the account of an address such as `MultiAddress::Id`.
+fn address_to_string(address: Value) -> String {
+    match address {
+        Value::String(address) => address,
+        Value::Object(map) if map.len() == 1 => map
+            .into_iter()
+            .next()
+            .map(|(_, account)| address_to_string(account))
+            .unwrap_or_default(),
+        address => address.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        extrinsic_params::PlainTip,
+        test_metadata::{
+            self, AccountId20, BalancesCall, EthereumSignature, RuntimeCall,
+        },
+    };
+    use sp_core::{ecdsa, ed25519};
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+    fn signer_bytes(
+        metadata: &Metadata,
+        address: &str,
+        scheme: SignatureScheme,
+        signature: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let mut out = vec![];
+        encode_signer(metadata, address, scheme, signature, &mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_same_address() {
+        assert!(same_address(ALICE, ALICE));
+        assert!(!same_address(ALICE, &ALICE.to_lowercase()));
+        assert!(!same_address(ALICE, BOB));
+
+        let checksummed = "0xf24FF3a9CF04c71Dbc94D0b566f7A27B94566cac";
+        assert!(same_address(checksummed, &checksummed.to_lowercase()));
+        assert!(!same_address(
+            checksummed,
+            "0x3Cd0A705a2DC65e5b1E1205896BaA2be8A07c6e0"
+        ));
+    }
+
+    #[test]
+    fn test_encode_multi_signer() {
+        let metadata = test_metadata::metadata();
+        let alice = MultiAddress::<AccountId32, u32>::Id(
+            AccountId32::from_ss58check(ALICE).unwrap(),
+        );
+
+        let ed25519 = ed25519::Signature::from_raw([1; 64]);
+        assert_eq!(
+            signer_bytes(&metadata, ALICE, SignatureScheme::Ed25519, &[1; 64])
+                .unwrap(),
+            (&alice, MultiSignature::Ed25519(ed25519)).encode()
+        );
+        let ecdsa = ecdsa::Signature::from_raw([2; 65]);
+        assert_eq!(
+            signer_bytes(&metadata, ALICE, SignatureScheme::Ecdsa, &[2; 65])
+                .unwrap(),
+            (&alice, MultiSignature::Ecdsa(ecdsa)).encode()
+        );
+
+        let err =
+            signer_bytes(&metadata, ALICE, SignatureScheme::Ed25519, &[1; 65])
+                .unwrap_err();
+        assert!(
+            matches!(err, Error::InvalidSignature(hex) if hex == "01".repeat(65))
+        );
+    }
+
+    #[test]
+    fn test_encode_ethereum_signer() {
+        let metadata = test_metadata::metadata_with::<
+            AccountId20,
+            AccountId20,
+            EthereumSignature,
+        >();
+        let address = format!("0x{}", hex::encode([3; 20]));
+        let signature = EthereumSignature(ecdsa::Signature::from_raw([2; 65]));
+        assert_eq!(
+            signer_bytes(
+                &metadata,
+                &address,
+                SignatureScheme::Ethereum,
+                &[2; 65]
+            )
+            .unwrap(),
+            (AccountId20([3; 20]), signature).encode()
+        );
+
+        let err = signer_bytes(
+            &metadata,
+            &address,
+            SignatureScheme::Ethereum,
+            &[2; 64],
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::InvalidSignature(_)));
+    }
+
+    #[test]
+    fn test_decode_built_extrinsic() {
+        let metadata = test_metadata::metadata();
+        let bob = AccountId32::from_ss58check(BOB).unwrap();
+        let call = RuntimeCall::Balances(BalancesCall::transfer {
+            dest: MultiAddress::<AccountId32, u32>::Id(bob),
+            value: 1_000,
+        });
+        let era = Era::mortal(64, 1000);
+        let params = ExtrinsicParams::new(
+            3,
+            100,
+            2,
+            H256::repeat_byte(1),
+            Some(era),
+            Some(H256::repeat_byte(2)),
+            Some(PlainTip::new(10)),
+        )
+        .with_fee_asset_id(Encoded(1984u32.encode()))
+        .with_signed_extensions(
+            metadata
+                .metadata
+                .extrinsic
+                .signed_extensions
+                .iter()
+                .map(|extension| extension.identifier.as_str()),
+        )
+        .unwrap();
+        let bytes = ExtrinsicBuilder::new(Encoded(call.encode()), params)
+            .encode_signed(&metadata, ALICE, SignatureScheme::Sr25519, &[7; 64])
+            .unwrap();
+
+        let decoded = decode_extrinsic(&metadata, &bytes).unwrap();
+        assert_eq!(decoded.hash, H256(sp_core::blake2_256(&bytes)));
+        assert_eq!(decoded.pallet, "Balances");
+        assert_eq!(decoded.call, "transfer");
+        assert_eq!(decoded.args, json!({"dest": {"Id": BOB}, "value": "1000"}));
+        let signature = decoded.signature.unwrap();
+        assert_eq!(signature.signer, ALICE);
+        assert_eq!(signature.signature_type, "Sr25519");
+        assert_eq!(
+            signature.signature,
+            json!(format!("0x{}", hex::encode([7; 64])))
+        );
+        assert_eq!(signature.era, era);
+        assert_eq!(signature.nonce, Some(3));
+        assert_eq!(signature.tip, Some(json!("10")));
+        assert_eq!(
+            signature.extensions["ChargeAssetTxPayment"]["asset_id"],
+            json!(1984)
+        );
+    }
+
+    #[test]
+    fn test_signature_schemes() {
+        assert_eq!(
+            SignatureScheme::from_account_type(None).unwrap(),
+            SignatureScheme::Sr25519
+        );
+        assert_eq!(
+            SignatureScheme::from_account_type(Some("ethereum")).unwrap(),
+            SignatureScheme::Ethereum
+        );
+        assert!(SignatureScheme::from_account_type(Some("bls")).is_err());
+
+        assert!(matches!(
+            SignatureScheme::Ed25519.multi_signature(&[1; 64]),
+            Some(MultiSignature::Ed25519(_))
+        ));
+        assert!(matches!(
+            SignatureScheme::Ethereum.multi_signature(&[1; 65]),
+            Some(MultiSignature::Ecdsa(_))
+        ));
+        let prefixed = [&[1u8][..], &[7; 64]].concat();
+        assert_eq!(
+            SignatureScheme::Sr25519.without_type_prefix(prefixed),
+            vec![7; 64]
+        );
+        assert_eq!(
+            SignatureScheme::Ecdsa.without_type_prefix(vec![7; 65]),
+            vec![7; 65]
+        );
+        // an ecdsa signature is not an sr25519 one
+        assert!(SignatureScheme::Sr25519.multi_signature(&[1; 65]).is_none());
     }
 }
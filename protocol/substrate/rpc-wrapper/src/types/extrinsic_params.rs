@@ -1,5 +1,11 @@
-use crate::utils::Encoded;
+//! The signed extensions of extrinsics, encoded in the order the chain metadata lists
+//! them.
+//!
+//! This file is **not** from subxt.
+
+use crate::{utils::Encoded, Error, ExtrinsicPayload};
 use codec::{Compact, Decode, Encode};
+use polywrap_wasm_rs::BigInt;
 use sp_core::H256;
 use sp_runtime::generic::Era;
 use sp_std::prelude::*;
@@ -11,7 +17,113 @@ pub struct PlainTip {
     tip: u128,
 }
 
-#[derive(Encode, Decode)]
+impl PlainTip {
+    /// Create a tip of `tip` in the smallest unit of the native or fee asset.
+    pub fn new(tip: u128) -> Self {
+        Self { tip }
+    }
+}
+
+/// How a signed extension is encoded: the data it adds to the extrinsic, and the data it
+/// only adds to the signed payload.
+struct SignedExtension {
+    identifier: &'static str,
+    extra: fn(&ExtrinsicParams, &mut Vec<u8>),
+    additional: fn(&ExtrinsicParams, &mut Vec<u8>),
+}
+
+/// The handlers of the signed extensions known to this crate.
+const SIGNED_EXTENSIONS: &[SignedExtension] = &[
+    SignedExtension {
+        identifier: "CheckNonZeroSender",
+        extra: |_, _| {},
+        additional: |_, _| {},
+    },
+    SignedExtension {
+        identifier: "CheckSpecVersion",
+        extra: |_, _| {},
+        additional: |params, v| params.spec_version.encode_to(v),
+    },
+    SignedExtension {
+        identifier: "CheckTxVersion",
+        extra: |_, _| {},
+        additional: |params, v| params.transaction_version.encode_to(v),
+    },
+    SignedExtension {
+        identifier: "CheckGenesis",
+        extra: |_, _| {},
+        additional: |params, v| params.genesis_hash.encode_to(v),
+    },
+    SignedExtension {
+        identifier: "CheckMortality",
+        extra: |params, v| params.era.encode_to(v),
+        additional: |params, v| params.mortality_checkpoint.encode_to(v),
+    },
+    // the name of `CheckMortality` before substrate 2.0
+    SignedExtension {
+        identifier: "CheckEra",
+        extra: |params, v| params.era.encode_to(v),
+        additional: |params, v| params.mortality_checkpoint.encode_to(v),
+    },
+    SignedExtension {
+        identifier: "CheckNonce",
+        extra: |params, v| Compact(params.nonce).encode_to(v),
+        additional: |_, _| {},
+    },
+    SignedExtension {
+        identifier: "CheckWeight",
+        extra: |_, _| {},
+        additional: |_, _| {},
+    },
+    SignedExtension {
+        identifier: "ChargeTransactionPayment",
+        extra: |params, v| Encoded(params.tip.encode()).encode_to(v),
+        additional: |_, _| {},
+    },
+    SignedExtension {
+        identifier: "ChargeAssetTxPayment",
+        extra: |params, v| {
+            params.tip.encode_to(v);
+            params.fee_asset_id.encode_to(v);
+        },
+        additional: |_, _| {},
+    },
+    // `Mode::Disabled`, without the hash of the metadata
+    SignedExtension {
+        identifier: "CheckMetadataHash",
+        extra: |_, v| 0u8.encode_to(v),
+        additional: |_, v| None::<H256>.encode_to(v),
+    },
+    SignedExtension {
+        identifier: "PrevalidateAttests",
+        extra: |_, _| {},
+        additional: |_, _| {},
+    },
+    SignedExtension {
+        identifier: "StorageWeightReclaim",
+        extra: |_, _| {},
+        additional: |_, _| {},
+    },
+];
+
+/// The signed extensions of the substrate node template, used until the params are given
+/// the extensions of the chain.
+const DEFAULT_SIGNED_EXTENSIONS: &[&str] = &[
+    "CheckSpecVersion",
+    "CheckTxVersion",
+    "CheckGenesis",
+    "CheckMortality",
+    "CheckNonce",
+    "CheckWeight",
+    "ChargeTransactionPayment",
+];
+
+fn signed_extension(identifier: &str) -> Option<&'static SignedExtension> {
+    SIGNED_EXTENSIONS
+        .iter()
+        .find(|extension| extension.identifier == identifier)
+}
+
 pub struct ExtrinsicParams {
     era: Era,
     nonce: u32,
@@ -20,6 +132,13 @@ pub struct ExtrinsicParams {
     genesis_hash: H256,
     mortality_checkpoint: H256,
     tip: PlainTip,
+    /// The SCALE encoded id of the asset `ChargeAssetTxPayment` pays the fees in, the
+    /// native token when `None`.
+    fee_asset_id: Option<Encoded>,
+    /// The number of the `mortality_checkpoint` block.
+    checkpoint_number: u32,
+    /// The signed extensions to encode, in order.
+    extensions: Vec<&'static SignedExtension>,
 }
 
 impl ExtrinsicParams {
@@ -41,24 +160,209 @@ impl ExtrinsicParams {
             transaction_version,
             genesis_hash,
             mortality_checkpoint: mortality_checkpoint.unwrap_or(genesis_hash),
+            fee_asset_id: None,
+            checkpoint_number: 0,
+            extensions: DEFAULT_SIGNED_EXTENSIONS
+                .iter()
+                .filter_map(|identifier| signed_extension(identifier))
+                .collect(),
         }
     }
 
+    /// Encode the signed extensions with the given `identifiers`, in their order, such as
+    /// the `signed_extensions` of the extrinsic metadata.
+    ///
+    /// Fails on the first extension with no known encoding, as the chain would reject the
+    /// signature of an extrinsic missing it.
+    pub fn with_signed_extensions<'a>(
+        mut self,
+        identifiers: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, Error> {
+        self.extensions = identifiers
+            .into_iter()
+            .map(|identifier| {
+                signed_extension(identifier).ok_or_else(|| {
+                    Error::UnknownSignedExtension(identifier.to_string())
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    /// Pay the fees in the asset with the SCALE encoded `fee_asset_id`, whose type depends
+    /// on the chain, through the `ChargeAssetTxPayment` extension.
+    pub fn with_fee_asset_id(mut self, fee_asset_id: Encoded) -> Self {
+        self.fee_asset_id = Some(fee_asset_id);
+        self
+    }
+
+    /// Set the number of the mortality checkpoint block, which signers show along the hash.
+    pub fn with_checkpoint_number(mut self, checkpoint_number: u32) -> Self {
+        self.checkpoint_number = checkpoint_number;
+        self
+    }
+
+    /// The payload of the extrinsic calling `method` for `signPayload`, letting wallets show
+    /// the decoded transaction to the signer with `address`.
+    ///
+    /// `None` when a signed extension has data the payload can't carry, such as a fee asset
+    /// id, in which case the extrinsic must be signed with `signRaw`.
+    pub fn signer_payload(
+        &self,
+        address: &str,
+        method: &Encoded,
+        version: u8,
+    ) -> Option<ExtrinsicPayload> {
+        if self.fee_asset_id.is_some() {
+            return None;
+        }
+        Some(ExtrinsicPayload {
+            address: address.to_string(),
+            block_hash: format!("{:#x}", self.mortality_checkpoint),
+            block_number: self.checkpoint_number,
+            era: format!("0x{}", hex::encode(self.era.encode())),
+            genesis_hash: format!("{:#x}", self.genesis_hash),
+            method: format!("0x{}", hex::encode(&method.0)),
+            nonce: self.nonce,
+            spec_version: self.spec_version,
+            tip: BigInt::from(self.tip.tip),
+            transaction_version: self.transaction_version,
+            signed_extensions: self
+                .extensions
+                .iter()
+                .map(|extension| extension.identifier.to_string())
+                .collect(),
+            version: version.into(),
+        })
+    }
+
     /// Encode extra params to buffer.
     pub fn encode_extra_to(&self, v: &mut Vec<u8>) {
-        let nonce: u64 = self.nonce.into();
-        let tip = Encoded(self.tip.encode());
-        (self.era, Compact(nonce), tip).encode_to(v);
+        for extension in &self.extensions {
+            (extension.extra)(self, v);
+        }
     }
 
     /// Encode additional params to buffer.
     pub fn encode_additional_to(&self, v: &mut Vec<u8>) {
-        (
-            self.spec_version,
-            self.transaction_version,
-            self.genesis_hash,
-            self.mortality_checkpoint,
+        for extension in &self.extensions {
+            (extension.additional)(self, v);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENESIS_HASH: H256 = H256([1; 32]);
+    const CHECKPOINT: H256 = H256([2; 32]);
+
+    /// Params of nonce 3 for spec version 100 and transaction version 2, checkpointed at
+    /// `CHECKPOINT`.
+    fn params(era: Era, tip: Option<PlainTip>) -> ExtrinsicParams {
+        ExtrinsicParams::new(
+            3,
+            100,
+            2,
+            GENESIS_HASH,
+            Some(era),
+            Some(CHECKPOINT),
+            tip,
         )
-            .encode_to(v);
+    }
+
+    #[test]
+    fn test_mortal_era_params() {
+        let era = Era::mortal(64, 1000);
+        let params = params(era, None);
+
+        let mut extra = vec![];
+        params.encode_extra_to(&mut extra);
+        assert_eq!(extra, (era, Compact(3u32), Compact(0u128)).encode());
+        assert_eq!(Era::decode(&mut extra.as_slice()).unwrap(), era);
+
+        let mut additional = vec![];
+        params.encode_additional_to(&mut additional);
+        assert_eq!(
+            additional,
+            (100u32, 2u32, GENESIS_HASH, CHECKPOINT).encode()
+        );
+    }
+
+    #[test]
+    fn test_signed_extensions_from_metadata() {
+        let era = Era::mortal(32, 1000);
+        let mortal = params(era, None)
+            .with_signed_extensions([
+                "CheckNonZeroSender",
+                "CheckGenesis",
+                "CheckNonce",
+                "CheckMortality",
+                "ChargeAssetTxPayment",
+                "CheckMetadataHash",
+            ])
+            .unwrap();
+
+        let mut extra = vec![];
+        mortal.encode_extra_to(&mut extra);
+        assert_eq!(
+            extra,
+            (Compact(3u32), era, Compact(0u128), None::<u32>, 0u8).encode()
+        );
+        let mut additional = vec![];
+        mortal.encode_additional_to(&mut additional);
+        assert_eq!(
+            additional,
+            (GENESIS_HASH, CHECKPOINT, None::<H256>).encode()
+        );
+
+        let unknown = params(Era::Immortal, None)
+            .with_signed_extensions(["CheckNonce", "CheckCustom"]);
+        assert!(matches!(
+            unknown,
+            Err(Error::UnknownSignedExtension(identifier)) if identifier == "CheckCustom"
+        ));
+    }
+
+    #[test]
+    fn test_charge_asset_tx_payment() {
+        let tipped = params(Era::Immortal, Some(PlainTip::new(1_000)))
+            .with_signed_extensions(["ChargeAssetTxPayment"])
+            .unwrap();
+        let mut extra = vec![];
+        tipped.encode_extra_to(&mut extra);
+        assert_eq!(extra, (Compact(1_000u128), None::<u32>).encode());
+
+        let in_asset = params(Era::Immortal, Some(PlainTip::new(1_000)))
+            .with_fee_asset_id(Encoded(1984u32.encode()))
+            .with_signed_extensions(["ChargeAssetTxPayment"])
+            .unwrap();
+        let mut extra = vec![];
+        in_asset.encode_extra_to(&mut extra);
+        assert_eq!(extra, (Compact(1_000u128), Some(1984u32)).encode());
+
+        // the payload has no field for the asset, the extrinsic is signed with `signRaw`
+        assert!(in_asset
+            .signer_payload("5Alice", &Encoded(vec![4, 0]), 4)
+            .is_none());
+    }
+
+    #[test]
+    fn test_signer_payload() {
+        let era = Era::mortal(64, 1000);
+        let params =
+            params(era, Some(PlainTip::new(10))).with_checkpoint_number(1000);
+
+        let payload = params
+            .signer_payload("5Alice", &Encoded(vec![4, 0]), 4)
+            .unwrap();
+        assert_eq!(payload.era, format!("0x{}", hex::encode(era.encode())));
+        assert_eq!(payload.block_hash, format!("{:#x}", CHECKPOINT));
+        assert_eq!(payload.block_number, 1000);
+        assert_eq!(payload.method, "0x0400");
+        assert_eq!(payload.nonce, 3);
+        assert_eq!(payload.tip, BigInt::from(10));
+        assert_eq!(payload.signed_extensions, DEFAULT_SIGNED_EXTENSIONS);
     }
 }
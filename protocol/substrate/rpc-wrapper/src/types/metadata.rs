@@ -2,18 +2,26 @@
 //!
 //! This file is mostly subxt.
 
-use crate::{types::storage::GetStorage, utils::Encoded};
+use crate::{
+    types::{metadata_versions, storage::GetStorage},
+    utils::Encoded,
+};
 use codec::{Encode, Error as CodecError};
 use frame_metadata::{
-    v14::StorageEntryType, PalletConstantMetadata, RuntimeMetadata,
-    RuntimeMetadataLastVersion, RuntimeMetadataPrefixed, StorageEntryMetadata,
-    META_RESERVED,
+    v14::{PalletConstantMetadata, StorageEntryMetadata, StorageEntryType},
+    v15::{OuterEnums, RuntimeApiMetadata, RuntimeMetadataLastVersion},
+    RuntimeMetadata, RuntimeMetadataPrefixed, META_RESERVED,
+};
+use scale_info::{
+    form::PortableForm, PortableRegistry, Type, TypeDef, Variant,
 };
-use scale_info::{form::PortableForm, Type, Variant};
 use serde::Serialize;
 use sp_core::storage::StorageKey;
 use std::{collections::HashMap, convert::TryFrom};
 
+/// The key and value types of a storage map.
+pub type StorageMapType<'a> = (&'a Type<PortableForm>, &'a Type<PortableForm>);
+
 /// Metadata error.
 #[derive(Debug, thiserror::Error)]
 pub enum MetadataError {
@@ -26,6 +34,9 @@ pub enum MetadataError {
     /// Call is not in metadata.
     #[error("Call {0} not found")]
     CallNotFound(String),
+    /// Call index is not in metadata.
+    #[error("Pallet {0}, Call {1} not found")]
+    CallIndexNotFound(u8, u8),
     /// Event is not in metadata.
     #[error("Pallet {0}, Event {0} not found")]
     EventNotFound(u8, u8),
@@ -40,6 +51,9 @@ pub enum MetadataError {
     StorageTypeError,
     #[error("Map value type error")]
     MapValueTypeError,
+    /// More keys given than the storage map has.
+    #[error("Storage map has {0} keys, got {1}")]
+    StorageKeyCount(usize, usize),
     /// Default error.
     #[error("Failed to decode default: {0}")]
     DefaultError(CodecError),
@@ -51,11 +65,21 @@ pub enum MetadataError {
     ConstantNotFound(String),
     #[error("Type {0} missing from type registry")]
     TypeNotFound(u32),
+    #[error("Type {0} not found in type registry")]
+    TypeNameNotFound(String),
+    #[error("Type {0} is not a variant type")]
+    TypeNotVariant(u32),
+    /// Legacy metadata only names its types.
+    #[error("Metadata V{0} has no type registry")]
+    NoTypeRegistry(u32),
 }
 
 /// Runtime metadata.
 #[derive(Clone, Debug)]
 pub struct Metadata {
+    /// The version of the metadata served by the chain, before it was normalized
+    /// into [`RuntimeMetadataLastVersion`].
+    pub version: u32,
     pub metadata: RuntimeMetadataLastVersion,
     pub pallets: HashMap<String, PalletMetadata>,
     pub events: HashMap<(u8, u8), EventMetadata>,
@@ -126,6 +150,23 @@ impl Metadata {
         &self.metadata
     }
 
+    /// Return the runtime api descriptors, only available from V15 onwards.
+    pub fn runtime_apis(&self) -> &[RuntimeApiMetadata<PortableForm>] {
+        &self.metadata.apis
+    }
+
+    /// Return the types of the outer call, event and error enums.
+    pub fn outer_enums(&self) -> &OuterEnums<PortableForm> {
+        &self.metadata.outer_enums
+    }
+
+    /// Whether the types in the registry describe the actual SCALE layout.
+    ///
+    /// Legacy metadata (before V14) only names its types, see [`metadata_versions`].
+    pub fn has_type_registry(&self) -> bool {
+        self.version >= 14
+    }
+
     pub fn storage_value_type(
         &self,
         pallet_name: &str,
@@ -138,10 +179,97 @@ impl Metadata {
             _ => None,
         };
         let portable_form =
-            ty_id.map(|ty_id| self.get_resolve_type(ty_id)).flatten();
+            ty_id.and_then(|ty_id| self.get_resolve_type(ty_id));
         Ok(portable_form)
     }
 
+    /// Return the type id of the values held by storage `storage_name`, for plain
+    /// storage values and storage maps alike.
+    pub fn storage_value_type_id(
+        &self,
+        pallet_name: &str,
+        storage_name: &str,
+    ) -> Result<u32, MetadataError> {
+        let pallet = self.pallet(pallet_name)?;
+        let storage_metadata = pallet.storage(storage_name)?;
+        match &storage_metadata.ty {
+            StorageEntryType::Plain(plain) => Ok(plain.id),
+            StorageEntryType::Map { value, .. } => Ok(value.id),
+        }
+    }
+
+    /// Return the type registry, provided the metadata has one that can be used to
+    /// encode and decode values.
+    pub fn type_registry(&self) -> Result<&PortableRegistry, MetadataError> {
+        if self.has_type_registry() {
+            Ok(&self.metadata.types)
+        } else {
+            Err(MetadataError::NoTypeRegistry(self.version))
+        }
+    }
+
+    /// Returns the variant of the pallet call enum describing `call_name` and its arguments.
+    pub fn call_variant(
+        &self,
+        pallet_name: &str,
+        call_name: &str,
+    ) -> Result<&Variant<PortableForm>, MetadataError> {
+        let call_not_found =
+            || MetadataError::CallNotFound(call_name.to_string());
+        let calls = self
+            .metadata
+            .pallets
+            .iter()
+            .find(|pallet| pallet.name == pallet_name)
+            .ok_or_else(|| {
+                MetadataError::PalletNotFound(pallet_name.to_string())
+            })?
+            .calls
+            .as_ref()
+            .ok_or_else(call_not_found)?;
+        let ty = self
+            .get_resolve_type(calls.ty.id)
+            .ok_or(MetadataError::TypeNotFound(calls.ty.id))?;
+        match &ty.type_def {
+            TypeDef::Variant(variant) => variant
+                .variants
+                .iter()
+                .find(|variant| variant.name == call_name)
+                .ok_or_else(call_not_found),
+            _ => Err(MetadataError::TypeNotVariant(calls.ty.id)),
+        }
+    }
+
+    /// Returns the name of the pallet at `pallet_index` and the variant of its call at
+    /// `call_index`, as found at the start of encoded call data.
+    pub fn call_variant_by_index(
+        &self,
+        pallet_index: u8,
+        call_index: u8,
+    ) -> Result<(&str, &Variant<PortableForm>), MetadataError> {
+        let call_not_found =
+            || MetadataError::CallIndexNotFound(pallet_index, call_index);
+        let pallet = self
+            .metadata
+            .pallets
+            .iter()
+            .find(|pallet| pallet.index == pallet_index)
+            .ok_or(MetadataError::PalletIndexNotFound(pallet_index))?;
+        let calls = pallet.calls.as_ref().ok_or_else(call_not_found)?;
+        let ty = self
+            .get_resolve_type(calls.ty.id)
+            .ok_or(MetadataError::TypeNotFound(calls.ty.id))?;
+        match &ty.type_def {
+            TypeDef::Variant(variant) => variant
+                .variants
+                .iter()
+                .find(|variant| variant.index == call_index)
+                .map(|variant| (pallet.name.as_str(), variant))
+                .ok_or_else(call_not_found),
+            _ => Err(MetadataError::TypeNotVariant(calls.ty.id)),
+        }
+    }
+
     pub fn pallet_call_index(
         &self,
         pallet_name: &str,
@@ -158,8 +286,7 @@ impl Metadata {
         &self,
         pallet_name: &str,
         storage_name: &str,
-    ) -> Result<Option<(&Type<PortableForm>, &Type<PortableForm>)>, MetadataError>
-    {
+    ) -> Result<Option<StorageMapType<'_>>, MetadataError> {
         let pallet = self.pallet(pallet_name)?;
         let storage_metadata = pallet.storage(storage_name)?;
         match storage_metadata.ty {
@@ -276,12 +403,18 @@ impl ErrorMetadata {
 pub enum InvalidMetadataError {
     #[error("Invalid prefix")]
     InvalidPrefix,
-    #[error("Invalid version")]
-    InvalidVersion,
+    #[error("Unsupported metadata version {0}")]
+    InvalidVersion(u32),
     #[error("Type {0} missing from type registry")]
     MissingType(u32),
     #[error("Type {0} was not a variant/enum type")]
     TypeDefNotVariant(u32),
+    #[error("Extrinsic type parameter {0} not found")]
+    MissingTypeParam(String),
+    #[error("Outer enum {0} not found")]
+    MissingOuterEnum(String),
+    #[error("Invalid legacy metadata")]
+    InvalidLegacyMetadata,
 }
 
 impl TryFrom<RuntimeMetadataPrefixed> for Metadata {
@@ -293,9 +426,13 @@ impl TryFrom<RuntimeMetadataPrefixed> for Metadata {
         if metadata.0 != META_RESERVED {
             return Err(InvalidMetadataError::InvalidPrefix);
         }
+        let version = metadata.1.version();
         let metadata = match metadata.1 {
-            RuntimeMetadata::V14(meta) => meta,
-            _ => return Err(InvalidMetadataError::InvalidVersion),
+            RuntimeMetadata::V15(meta) => meta,
+            RuntimeMetadata::V14(meta) => metadata_versions::v14_to_v15(meta)?,
+            RuntimeMetadata::V13(meta) => metadata_versions::v13_to_v15(meta)?,
+            RuntimeMetadata::V12(meta) => metadata_versions::v12_to_v15(meta)?,
+            _ => return Err(InvalidMetadataError::InvalidVersion(version)),
         };
 
         let get_type_def_variant = |type_id: u32| {
@@ -406,6 +543,7 @@ impl TryFrom<RuntimeMetadataPrefixed> for Metadata {
             .collect();
 
         Ok(Self {
+            version,
             metadata,
             pallets,
             events,
@@ -466,4 +604,34 @@ impl Metadata {
             .get_double_map::<K, Q>(storage_prefix)?
             .key(first, second))
     }
+
+    /// Key of a map with any number of keys, from the SCALE encoded `keys`. Fewer keys
+    /// than the map has give the prefix of the entries starting with those keys.
+    pub fn storage_n_map_key(
+        &self,
+        storage_prefix: &str,
+        storage_key_name: &str,
+        keys: &[Vec<u8>],
+    ) -> Result<StorageKey, MetadataError> {
+        self.pallet(storage_prefix)?
+            .storage(storage_key_name)?
+            .get_n_map(storage_prefix)?
+            .key(keys)
+    }
+
+    /// Find the pallet and storage entry whose twox128 prefixes start `storage_key`.
+    pub fn storage_entry_by_key(
+        &self,
+        storage_key: &[u8],
+    ) -> Option<(&PalletMetadata, &StorageEntryMetadata<PortableForm>)> {
+        let pallet_hash = storage_key.get(..16)?;
+        let storage_hash = storage_key.get(16..32)?;
+        let pallet = self.pallets.values().find(|pallet| {
+            sp_core::twox_128(pallet.name.as_bytes()) == pallet_hash
+        })?;
+        let entry = pallet.storage.values().find(|entry| {
+            sp_core::twox_128(entry.name.as_bytes()) == storage_hash
+        })?;
+        Some((pallet, entry))
+    }
 }
@@ -0,0 +1,684 @@
+//! Compare two versions of the runtime metadata to find the changes which break clients,
+//! such as shifted call indices or changed argument and storage types.
+//!
+//! This file is **not** from subxt.
+
+use crate::types::metadata::Metadata;
+use frame_metadata::{
+    v14::{StorageEntryMetadata, StorageEntryType},
+    v15::PalletMetadata as RuntimePalletMetadata,
+};
+use scale_info::{form::PortableForm, Field, TypeDef, Variant};
+use std::collections::HashMap;
+
+/// Whether an item was added, removed or changed between the two versions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// The changes to a call, event or storage entry of a pallet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ItemDiff {
+    pub name: String,
+    pub change: Change,
+    /// Readable description of each change, such as `index 3 -> 4`.
+    pub changes: Vec<String>,
+}
+
+/// The changes to a pallet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PalletDiff {
+    pub name: String,
+    pub change: Change,
+    /// Changes to the pallet itself, such as its index.
+    pub changes: Vec<String>,
+    pub calls: Vec<ItemDiff>,
+    pub storage: Vec<ItemDiff>,
+    pub events: Vec<ItemDiff>,
+}
+
+/// Return the pallets which differ between the `old` and `new` metadata.
+///
+/// A shifted pallet index is reported on the pallet only, although it also changes the
+/// index of all its calls and events.
+pub fn diff(old: &Metadata, new: &Metadata) -> Vec<PalletDiff> {
+    let mut differ = Differ {
+        old,
+        new,
+        same_types: HashMap::new(),
+        comparing: HashMap::new(),
+        assumed_depth: usize::MAX,
+    };
+    let mut pallets = vec![];
+
+    for old_pallet in &old.metadata.pallets {
+        let new_pallet = new
+            .metadata
+            .pallets
+            .iter()
+            .find(|pallet| pallet.name == old_pallet.name);
+        match new_pallet {
+            Some(new_pallet) => {
+                pallets.extend(differ.diff_pallets(old_pallet, new_pallet))
+            }
+            None => pallets.push(PalletDiff {
+                name: old_pallet.name.clone(),
+                change: Change::Removed,
+                changes: vec![],
+                calls: vec![],
+                storage: vec![],
+                events: vec![],
+            }),
+        }
+    }
+    for new_pallet in &new.metadata.pallets {
+        if !old
+            .metadata
+            .pallets
+            .iter()
+            .any(|pallet| pallet.name == new_pallet.name)
+        {
+            pallets.push(PalletDiff {
+                name: new_pallet.name.clone(),
+                change: Change::Added,
+                changes: vec![],
+                calls: vec![],
+                storage: vec![],
+                events: vec![],
+            });
+        }
+    }
+    pallets
+}
+
+struct Differ<'a> {
+    old: &'a Metadata,
+    new: &'a Metadata,
+    /// memo of the (old, new) type pairs already compared
+    same_types: HashMap<(u32, u32), bool>,
+    /// the type pairs being compared with their depth, to stop at recursive types
+    comparing: HashMap<(u32, u32), usize>,
+    /// the lowest depth of the recursive pairs assumed the same in the current comparison
+    assumed_depth: usize,
+}
+
+impl<'a> Differ<'a> {
+    /// Diff two versions of a pallet, `None` if it didn't change.
+    fn diff_pallets(
+        &mut self,
+        old: &RuntimePalletMetadata<PortableForm>,
+        new: &RuntimePalletMetadata<PortableForm>,
+    ) -> Option<PalletDiff> {
+        let mut changes = vec![];
+        if old.index != new.index {
+            changes.push(format!("index {} -> {}", old.index, new.index));
+        }
+
+        let old_calls = variants(self.old, old.calls.as_ref().map(|c| c.ty.id));
+        let new_calls = variants(self.new, new.calls.as_ref().map(|c| c.ty.id));
+        let calls = self.diff_variants(old_calls, new_calls, "argument");
+
+        let old_events =
+            variants(self.old, old.event.as_ref().map(|e| e.ty.id));
+        let new_events =
+            variants(self.new, new.event.as_ref().map(|e| e.ty.id));
+        let events = self.diff_variants(old_events, new_events, "field");
+
+        let old_storage = storage_entries(old);
+        let new_storage = storage_entries(new);
+        let mut storage = vec![];
+        for old_entry in old_storage {
+            match new_storage
+                .iter()
+                .find(|entry| entry.name == old_entry.name)
+            {
+                Some(new_entry) => {
+                    let entry_changes =
+                        self.diff_storage_entries(old_entry, new_entry);
+                    if !entry_changes.is_empty() {
+                        storage.push(ItemDiff {
+                            name: old_entry.name.clone(),
+                            change: Change::Changed,
+                            changes: entry_changes,
+                        });
+                    }
+                }
+                None => storage.push(removed(&old_entry.name)),
+            }
+        }
+        for new_entry in new_storage {
+            if !old_storage.iter().any(|entry| entry.name == new_entry.name) {
+                storage.push(added(&new_entry.name));
+            }
+        }
+
+        if changes.is_empty()
+            && calls.is_empty()
+            && storage.is_empty()
+            && events.is_empty()
+        {
+            return None;
+        }
+        Some(PalletDiff {
+            name: old.name.clone(),
+            change: Change::Changed,
+            changes,
+            calls,
+            storage,
+            events,
+        })
+    }
+
+    /// Diff calls or events, reporting index shifts and field changes.
+    fn diff_variants(
+        &mut self,
+        old: &[Variant<PortableForm>],
+        new: &[Variant<PortableForm>],
+        field_kind: &str,
+    ) -> Vec<ItemDiff> {
+        let mut diffs = vec![];
+        for old_variant in old {
+            match new.iter().find(|variant| variant.name == old_variant.name) {
+                Some(new_variant) => {
+                    let mut changes = vec![];
+                    if old_variant.index != new_variant.index {
+                        changes.push(format!(
+                            "index {} -> {}",
+                            old_variant.index, new_variant.index
+                        ));
+                    }
+                    changes.extend(self.diff_fields(
+                        &old_variant.fields,
+                        &new_variant.fields,
+                        field_kind,
+                    ));
+                    if !changes.is_empty() {
+                        diffs.push(ItemDiff {
+                            name: old_variant.name.clone(),
+                            change: Change::Changed,
+                            changes,
+                        });
+                    }
+                }
+                None => diffs.push(removed(&old_variant.name)),
+            }
+        }
+        for new_variant in new {
+            if !old.iter().any(|variant| variant.name == new_variant.name) {
+                diffs.push(added(&new_variant.name));
+            }
+        }
+        diffs
+    }
+
+    fn diff_fields(
+        &mut self,
+        old: &[Field<PortableForm>],
+        new: &[Field<PortableForm>],
+        field_kind: &str,
+    ) -> Vec<String> {
+        let mut changes = vec![];
+        for position in 0..old.len().max(new.len()) {
+            match (old.get(position), new.get(position)) {
+                (Some(old_field), Some(new_field)) => {
+                    let name = field_name(new_field, position);
+                    if old_field.name != new_field.name {
+                        changes.push(format!(
+                            "{} {} renamed from `{}`",
+                            field_kind,
+                            name,
+                            field_name(old_field, position)
+                        ));
+                    }
+                    if let Some(change) =
+                        self.diff_types(old_field.ty.id, new_field.ty.id)
+                    {
+                        changes.push(format!(
+                            "{} {} type {}",
+                            field_kind, name, change
+                        ));
+                    }
+                }
+                (Some(old_field), None) => changes.push(format!(
+                    "{} {} removed",
+                    field_kind,
+                    field_name(old_field, position)
+                )),
+                (None, Some(new_field)) => changes.push(format!(
+                    "{} {} added",
+                    field_kind,
+                    field_name(new_field, position)
+                )),
+                (None, None) => {}
+            }
+        }
+        changes
+    }
+
+    fn diff_storage_entries(
+        &mut self,
+        old: &StorageEntryMetadata<PortableForm>,
+        new: &StorageEntryMetadata<PortableForm>,
+    ) -> Vec<String> {
+        let mut changes = vec![];
+        if old.modifier != new.modifier {
+            changes.push(format!(
+                "modifier {:?} -> {:?}",
+                old.modifier, new.modifier
+            ));
+        }
+        match (&old.ty, &new.ty) {
+            (
+                StorageEntryType::Plain(old_value),
+                StorageEntryType::Plain(new_value),
+            ) => {
+                if let Some(change) =
+                    self.diff_types(old_value.id, new_value.id)
+                {
+                    changes.push(format!("value type {}", change));
+                }
+            }
+            (
+                StorageEntryType::Map {
+                    hashers: old_hashers,
+                    key: old_key,
+                    value: old_value,
+                },
+                StorageEntryType::Map {
+                    hashers: new_hashers,
+                    key: new_key,
+                    value: new_value,
+                },
+            ) => {
+                if old_hashers != new_hashers {
+                    changes.push(format!(
+                        "hashers {:?} -> {:?}",
+                        old_hashers, new_hashers
+                    ));
+                }
+                if let Some(change) = self.diff_types(old_key.id, new_key.id) {
+                    changes.push(format!("key type {}", change));
+                }
+                if let Some(change) =
+                    self.diff_types(old_value.id, new_value.id)
+                {
+                    changes.push(format!("value type {}", change));
+                }
+            }
+            (StorageEntryType::Plain(_), _) => {
+                changes.push("changed from a plain value to a map".to_string())
+            }
+            (_, StorageEntryType::Plain(_)) => {
+                changes.push("changed from a map to a plain value".to_string())
+            }
+        }
+        changes
+    }
+
+    /// Describe how the type changed, if it did.
+    fn diff_types(&mut self, old_id: u32, new_id: u32) -> Option<String> {
+        let old_name = self.old.type_name(old_id);
+        let new_name = self.new.type_name(new_id);
+        if old_name != new_name {
+            Some(format!("{} -> {}", old_name, new_name))
+        } else if !self.same_type(old_id, new_id) {
+            Some(format!("{} changed layout", new_name))
+        } else {
+            None
+        }
+    }
+
+    /// Compare the SCALE layout of two types, including the field and variant names
+    /// which show up in their json.
+    fn same_type(&mut self, old_id: u32, new_id: u32) -> bool {
+        let key = (old_id, new_id);
+        if let Some(same) = self.same_types.get(&key) {
+            return *same;
+        }
+        // a recursive type is the same as long as the rest of it is
+        let depth = self.comparing.len();
+        if let Some(&pair_depth) = self.comparing.get(&key) {
+            self.assumed_depth = self.assumed_depth.min(pair_depth);
+            return true;
+        }
+        self.comparing.insert(key, depth);
+        let outer_assumed_depth =
+            std::mem::replace(&mut self.assumed_depth, usize::MAX);
+
+        let same = match (
+            self.old.get_resolve_type(old_id),
+            self.new.get_resolve_type(new_id),
+        ) {
+            (Some(old_ty), Some(new_ty)) => {
+                self.same_type_def(&old_ty.type_def, &new_ty.type_def)
+            }
+            _ => false,
+        };
+
+        self.comparing.remove(&key);
+        // the same only if the pairs still being compared above are, so memoize it once
+        // they are done, a difference holds whatever they turn out to be
+        if self.assumed_depth >= depth {
+            self.same_types.insert(key, same);
+            self.assumed_depth = outer_assumed_depth;
+        } else {
+            if !same {
+                self.same_types.insert(key, same);
+            }
+            self.assumed_depth = outer_assumed_depth.min(self.assumed_depth);
+        }
+        same
+    }
+
+    fn same_type_def(
+        &mut self,
+        old: &TypeDef<PortableForm>,
+        new: &TypeDef<PortableForm>,
+    ) -> bool {
+        match (old, new) {
+            (TypeDef::Composite(old), TypeDef::Composite(new)) => {
+                self.same_fields(&old.fields, &new.fields)
+            }
+            (TypeDef::Variant(old), TypeDef::Variant(new)) => {
+                old.variants.len() == new.variants.len()
+                    && old.variants.iter().zip(&new.variants).all(
+                        |(old, new)| {
+                            old.name == new.name
+                                && old.index == new.index
+                                && self.same_fields(&old.fields, &new.fields)
+                        },
+                    )
+            }
+            (TypeDef::Sequence(old), TypeDef::Sequence(new)) => {
+                self.same_type(old.type_param.id, new.type_param.id)
+            }
+            (TypeDef::Array(old), TypeDef::Array(new)) => {
+                old.len == new.len
+                    && self.same_type(old.type_param.id, new.type_param.id)
+            }
+            (TypeDef::Tuple(old), TypeDef::Tuple(new)) => {
+                old.fields.len() == new.fields.len()
+                    && old
+                        .fields
+                        .iter()
+                        .zip(&new.fields)
+                        .all(|(old, new)| self.same_type(old.id, new.id))
+            }
+            (TypeDef::Primitive(old), TypeDef::Primitive(new)) => old == new,
+            (TypeDef::Compact(old), TypeDef::Compact(new)) => {
+                self.same_type(old.type_param.id, new.type_param.id)
+            }
+            (TypeDef::BitSequence(old), TypeDef::BitSequence(new)) => {
+                self.same_type(old.bit_store_type.id, new.bit_store_type.id)
+                    && self
+                        .same_type(old.bit_order_type.id, new.bit_order_type.id)
+            }
+            _ => false,
+        }
+    }
+
+    fn same_fields(
+        &mut self,
+        old: &[Field<PortableForm>],
+        new: &[Field<PortableForm>],
+    ) -> bool {
+        old.len() == new.len()
+            && old.iter().zip(new).all(|(old, new)| {
+                old.name == new.name && self.same_type(old.ty.id, new.ty.id)
+            })
+    }
+}
+
+fn variants(
+    metadata: &Metadata,
+    type_id: Option<u32>,
+) -> &[Variant<PortableForm>] {
+    let type_def = type_id
+        .and_then(|type_id| metadata.get_resolve_type(type_id))
+        .map(|ty| &ty.type_def);
+    match type_def {
+        Some(TypeDef::Variant(variant)) => &variant.variants,
+        _ => &[],
+    }
+}
+
+fn storage_entries(
+    pallet: &RuntimePalletMetadata<PortableForm>,
+) -> &[StorageEntryMetadata<PortableForm>] {
+    pallet
+        .storage
+        .as_ref()
+        .map(|storage| storage.entries.as_slice())
+        .unwrap_or_default()
+}
+
+fn field_name(field: &Field<PortableForm>, position: usize) -> String {
+    match &field.name {
+        Some(name) => format!("`{}`", name),
+        None => format!("#{}", position),
+    }
+}
+
+fn added(name: &str) -> ItemDiff {
+    ItemDiff {
+        name: name.to_string(),
+        change: Change::Added,
+        changes: vec![],
+    }
+}
+
+fn removed(name: &str) -> ItemDiff {
+    ItemDiff {
+        name: name.to_string(),
+        change: Change::Removed,
+        changes: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_metadata;
+    use codec::{Decode, Encode};
+    use frame_metadata::{
+        decode_different::DecodeDifferent,
+        v13,
+        v13::{
+            ExtrinsicMetadata, FunctionArgumentMetadata, FunctionMetadata,
+            ModuleMetadata, RuntimeMetadataV13, StorageEntryModifier,
+            StorageHasher, StorageMetadata,
+        },
+        v14, RuntimeMetadata, RuntimeMetadataPrefixed, META_RESERVED,
+    };
+    use std::convert::TryFrom;
+
+    fn d<B, T>(value: T) -> DecodeDifferent<B, T> {
+        DecodeDifferent::Decoded(value)
+    }
+
+    fn call(name: &str, args: &[(&str, &str)]) -> FunctionMetadata {
+        FunctionMetadata {
+            name: d(name.to_string()),
+            arguments: d(args
+                .iter()
+                .map(|(name, ty)| FunctionArgumentMetadata {
+                    name: d(name.to_string()),
+                    ty: d(ty.to_string()),
+                })
+                .collect()),
+            documentation: d(vec![]),
+        }
+    }
+
+    fn module(
+        name: &str,
+        index: u8,
+        calls: Vec<FunctionMetadata>,
+        hasher: StorageHasher,
+    ) -> ModuleMetadata {
+        ModuleMetadata {
+            name: d(name.to_string()),
+            storage: Some(d(StorageMetadata {
+                prefix: d(name.to_string()),
+                entries: d(vec![v13::StorageEntryMetadata {
+                    name: d("Account".to_string()),
+                    modifier: StorageEntryModifier::Default,
+                    ty: v13::StorageEntryType::Map {
+                        hasher,
+                        key: d("T::AccountId".to_string()),
+                        value: d("AccountData".to_string()),
+                        unused: false,
+                    },
+                    default: d(vec![]),
+                    documentation: d(vec![]),
+                }]),
+            })),
+            calls: Some(d(calls)),
+            event: None,
+            constants: d(vec![]),
+            errors: d(vec![]),
+            index,
+        }
+    }
+
+    fn metadata(modules: Vec<ModuleMetadata>) -> Metadata {
+        let v13 = RuntimeMetadataV13 {
+            modules: d(modules),
+            extrinsic: ExtrinsicMetadata {
+                version: 4,
+                signed_extensions: vec![],
+            },
+        };
+        let encoded =
+            RuntimeMetadataPrefixed(META_RESERVED, RuntimeMetadata::V13(v13))
+                .encode();
+        let prefixed =
+            RuntimeMetadataPrefixed::decode(&mut encoded.as_slice()).unwrap();
+        Metadata::try_from(prefixed).unwrap()
+    }
+
+    #[test]
+    fn test_metadata_diff() {
+        let transfer = |value: &str| {
+            call("transfer", &[("dest", "T::AccountId"), ("value", value)])
+        };
+        let old = metadata(vec![module(
+            "Balances",
+            5,
+            vec![transfer("Compact<T::Balance>")],
+            StorageHasher::Blake2_128Concat,
+        )]);
+        let new = metadata(vec![
+            module(
+                "Balances",
+                5,
+                vec![call("transfer_all", &[]), transfer("T::Balance")],
+                StorageHasher::Twox64Concat,
+            ),
+            module("Sudo", 6, vec![], StorageHasher::Twox64Concat),
+        ]);
+
+        assert_eq!(diff(&old, &old), vec![]);
+
+        let pallets = diff(&old, &new);
+        assert_eq!(pallets.len(), 2);
+        let balances = &pallets[0];
+        assert_eq!(balances.change, Change::Changed);
+        assert_eq!(
+            balances.calls,
+            vec![
+                ItemDiff {
+                    name: "transfer".to_string(),
+                    change: Change::Changed,
+                    changes: vec![
+                        "index 0 -> 1".to_string(),
+                        "argument `value` type Compact<T::Balance> -> \
+                         T::Balance"
+                            .to_string(),
+                    ],
+                },
+                added("transfer_all"),
+            ]
+        );
+        assert_eq!(
+            balances.storage[0].changes,
+            vec!["hashers [Blake2_128Concat] -> [Twox64Concat]".to_string()]
+        );
+        assert_eq!(pallets[1].name, "Sudo");
+        assert_eq!(pallets[1].change, Change::Added);
+    }
+
+    // the trees only describe the storage through their `TypeInfo`
+    #[allow(dead_code)]
+    mod old {
+        #[derive(scale_info::TypeInfo)]
+        pub struct Tree {
+            pub children: Vec<Tree>,
+            pub value: u32,
+        }
+    }
+
+    #[allow(dead_code)]
+    mod new {
+        #[derive(scale_info::TypeInfo)]
+        pub struct Tree {
+            pub children: Vec<Tree>,
+            pub value: u64,
+        }
+    }
+
+    fn tree_metadata<Tree: scale_info::TypeInfo + 'static>() -> Metadata {
+        let entry = |name, ty| v14::StorageEntryMetadata {
+            name,
+            modifier: v14::StorageEntryModifier::Default,
+            ty: v14::StorageEntryType::Plain(ty),
+            default: vec![0],
+            docs: vec![],
+        };
+        let pallet = v14::PalletMetadata {
+            name: "Trees",
+            storage: Some(v14::PalletStorageMetadata {
+                prefix: "Trees",
+                entries: vec![
+                    entry("Tree", scale_info::meta_type::<Tree>()),
+                    entry("Forest", scale_info::meta_type::<Vec<Tree>>()),
+                ],
+            }),
+            calls: None,
+            event: None,
+            constants: vec![],
+            error: None,
+            index: 0,
+        };
+        test_metadata::metadata_with_pallets(vec![pallet])
+    }
+
+    #[test]
+    fn test_recursive_type_diff() {
+        let old = tree_metadata::<old::Tree>();
+        let new = tree_metadata::<new::Tree>();
+        assert_eq!(diff(&old, &old), vec![]);
+
+        let pallets = diff(&old, &new);
+        assert_eq!(pallets.len(), 1);
+        let changes = |name: &str| {
+            pallets[0]
+                .storage
+                .iter()
+                .find(|entry| entry.name == name)
+                .map(|entry| entry.changes.clone())
+        };
+        assert_eq!(
+            changes("Tree"),
+            Some(vec!["value type Tree changed layout".to_string()])
+        );
+        // the sequence was compared while assuming the recursive `Tree` the same
+        assert_eq!(
+            changes("Forest"),
+            Some(vec!["value type Vec<Tree> changed layout".to_string()])
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Typed views of the pallets in the metadata, for introspection.
+//!
+//! This file is **not** from subxt.
+
+use crate::types::metadata::{Metadata, MetadataError};
+use frame_metadata::{
+    v14::{StorageEntryMetadata, StorageEntryType},
+    v15::PalletMetadata as RuntimePalletMetadata,
+};
+use scale_info::{form::PortableForm, TypeDef, Variant};
+
+impl Metadata {
+    /// Return the pallets in the order they appear in the metadata.
+    pub fn runtime_pallets(&self) -> &[RuntimePalletMetadata<PortableForm>] {
+        &self.metadata.pallets
+    }
+
+    fn runtime_pallet(
+        &self,
+        pallet_name: &str,
+    ) -> Result<&RuntimePalletMetadata<PortableForm>, MetadataError> {
+        self.metadata
+            .pallets
+            .iter()
+            .find(|pallet| pallet.name == pallet_name)
+            .ok_or_else(|| {
+                MetadataError::PalletNotFound(pallet_name.to_string())
+            })
+    }
+
+    /// The variants of the enum `type_id`, or none for pallets without such an enum.
+    fn enum_variants(
+        &self,
+        type_id: Option<u32>,
+    ) -> Result<&[Variant<PortableForm>], MetadataError> {
+        let type_id = match type_id {
+            Some(type_id) => type_id,
+            None => return Ok(&[]),
+        };
+        let ty = self
+            .get_resolve_type(type_id)
+            .ok_or(MetadataError::TypeNotFound(type_id))?;
+        match &ty.type_def {
+            TypeDef::Variant(variant) => Ok(&variant.variants),
+            _ => Err(MetadataError::TypeNotVariant(type_id)),
+        }
+    }
+
+    /// Return the calls of `pallet_name`, with their arguments as variant fields.
+    pub fn pallet_calls(
+        &self,
+        pallet_name: &str,
+    ) -> Result<&[Variant<PortableForm>], MetadataError> {
+        let pallet = self.runtime_pallet(pallet_name)?;
+        self.enum_variants(pallet.calls.as_ref().map(|calls| calls.ty.id))
+    }
+
+    /// Return the events of `pallet_name`, with their fields as variant fields.
+    pub fn pallet_events(
+        &self,
+        pallet_name: &str,
+    ) -> Result<&[Variant<PortableForm>], MetadataError> {
+        let pallet = self.runtime_pallet(pallet_name)?;
+        self.enum_variants(pallet.event.as_ref().map(|event| event.ty.id))
+    }
+
+    /// Return the errors of `pallet_name`.
+    pub fn pallet_errors(
+        &self,
+        pallet_name: &str,
+    ) -> Result<&[Variant<PortableForm>], MetadataError> {
+        let pallet = self.runtime_pallet(pallet_name)?;
+        self.enum_variants(pallet.error.as_ref().map(|error| error.ty.id))
+    }
+
+    /// Return the storage entries of `pallet_name` in the order they are declared.
+    pub fn pallet_storage_entries(
+        &self,
+        pallet_name: &str,
+    ) -> Result<&[StorageEntryMetadata<PortableForm>], MetadataError> {
+        let pallet = self.runtime_pallet(pallet_name)?;
+        Ok(pallet
+            .storage
+            .as_ref()
+            .map(|storage| storage.entries.as_slice())
+            .unwrap_or_default())
+    }
+
+    /// Return the type of each key of a storage map, one per hasher.
+    ///
+    /// The key of double maps and NMaps is a tuple of the keys.
+    pub fn storage_key_type_ids(
+        &self,
+        entry: &StorageEntryMetadata<PortableForm>,
+    ) -> Vec<u32> {
+        match &entry.ty {
+            StorageEntryType::Plain(_) => vec![],
+            StorageEntryType::Map { hashers, key, .. } => {
+                match self.get_resolve_type(key.id).map(|ty| &ty.type_def) {
+                    Some(TypeDef::Tuple(tuple)) if hashers.len() > 1 => {
+                        tuple.fields.iter().map(|field| field.id).collect()
+                    }
+                    _ => vec![key.id],
+                }
+            }
+        }
+    }
+
+    /// Return a readable name for the type `type_id`, such as `Vec<u8>` or
+    /// `Option<AccountId32>`.
+    pub fn type_name(&self, type_id: u32) -> String {
+        let ty = match self.get_resolve_type(type_id) {
+            Some(ty) => ty,
+            None => return format!("<unknown type {}>", type_id),
+        };
+        match &ty.type_def {
+            TypeDef::Composite(_) | TypeDef::Variant(_) => {
+                let name = ty.path.segments.last().cloned().unwrap_or_default();
+                let params: Vec<String> = ty
+                    .type_params
+                    .iter()
+                    .filter_map(|param| {
+                        param.ty.map(|ty| self.type_name(ty.id))
+                    })
+                    .collect();
+                if params.is_empty() {
+                    name
+                } else {
+                    format!("{}<{}>", name, params.join(", "))
+                }
+            }
+            TypeDef::Sequence(sequence) => {
+                format!("Vec<{}>", self.type_name(sequence.type_param.id))
+            }
+            TypeDef::Array(array) => format!(
+                "[{}; {}]",
+                self.type_name(array.type_param.id),
+                array.len
+            ),
+            TypeDef::Tuple(tuple) => {
+                let fields: Vec<String> = tuple
+                    .fields
+                    .iter()
+                    .map(|field| self.type_name(field.id))
+                    .collect();
+                format!("({})", fields.join(", "))
+            }
+            TypeDef::Primitive(primitive) => {
+                format!("{:?}", primitive).to_lowercase()
+            }
+            TypeDef::Compact(compact) => {
+                format!("Compact<{}>", self.type_name(compact.type_param.id))
+            }
+            TypeDef::BitSequence(_) => "BitVec".to_string(),
+        }
+    }
+}
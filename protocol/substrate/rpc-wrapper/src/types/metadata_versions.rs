@@ -0,0 +1,680 @@
+//! Normalize the supported runtime metadata versions into the latest one.
+//!
+//! Newer nodes serve V15 through the `Metadata_metadata_at_version` runtime api,
+//! while `state_getMetadata` returns V14 or, on older chains, the legacy V13/V12 formats.
+//! The rest of the wrapper only deals with [`RuntimeMetadataLastVersion`], so the older
+//! versions are upgraded here.
+//!
+//! This file is **not** from subxt.
+
+use crate::types::metadata::InvalidMetadataError;
+use codec::{Decode, Encode};
+use frame_metadata::{
+    decode_different::DecodeDifferent,
+    v12::RuntimeMetadataV12,
+    v13::{self, RuntimeMetadataV13},
+    v14::{
+        self, PalletCallMetadata, PalletConstantMetadata, PalletErrorMetadata,
+        PalletEventMetadata, PalletStorageMetadata, RuntimeMetadataV14,
+        StorageEntryMetadata, StorageEntryModifier, StorageEntryType,
+        StorageHasher,
+    },
+    v15::{
+        CustomMetadata, ExtrinsicMetadata, OuterEnums, PalletMetadata,
+        RuntimeMetadataLastVersion, SignedExtensionMetadata,
+    },
+};
+use scale_info::{
+    form::PortableForm, interner::UntrackedSymbol, Field, Path,
+    PortableRegistry, PortableRegistryBuilder, PortableType, Type, TypeDef,
+    TypeDefComposite, TypeDefTuple, TypeDefVariant, Variant,
+};
+use std::{any::TypeId, collections::BTreeMap};
+
+type TypeSymbol = UntrackedSymbol<TypeId>;
+
+/// Upgrade V14 metadata to V15.
+///
+/// V14 has no runtime api descriptors, so those are left empty. The outer call and event
+/// enums are looked up in the type registry, while the outer error enum does not exist in
+/// V14 and is synthesized from the pallet error types.
+pub fn v14_to_v15(
+    metadata: RuntimeMetadataV14,
+) -> Result<RuntimeMetadataLastVersion, InvalidMetadataError> {
+    let RuntimeMetadataV14 {
+        mut types,
+        pallets,
+        extrinsic,
+        ty,
+    } = metadata;
+
+    let extrinsic_ty_id = extrinsic.ty.id;
+    let extrinsic_ty = types
+        .resolve(extrinsic_ty_id)
+        .ok_or(InvalidMetadataError::MissingType(extrinsic_ty_id))?;
+    let type_param = |name: &str| {
+        extrinsic_ty
+            .type_params
+            .iter()
+            .find(|param| param.name == name)
+            .and_then(|param| param.ty)
+            .ok_or_else(|| {
+                InvalidMetadataError::MissingTypeParam(name.to_string())
+            })
+    };
+    let address_ty = type_param("Address")?;
+    let call_ty = type_param("Call")?;
+    let signature_ty = type_param("Signature")?;
+    let extra_ty = type_param("Extra")?;
+    let event_enum_ty = find_event_enum(&types, &pallets)?;
+
+    let error_variants = pallets
+        .iter()
+        .filter_map(|pallet| {
+            pallet.error.as_ref().map(|error| Variant {
+                name: pallet.name.clone(),
+                fields: vec![unnamed_field(error.ty, None)],
+                index: pallet.index,
+                docs: vec![],
+            })
+        })
+        .collect();
+    let error_enum_ty = push_type(
+        &mut types,
+        Type {
+            path: Path {
+                segments: vec!["RuntimeError".to_string()],
+            },
+            type_params: vec![],
+            type_def: TypeDef::Variant(TypeDefVariant {
+                variants: error_variants,
+            }),
+            docs: vec![],
+        },
+    );
+
+    let pallets = pallets
+        .into_iter()
+        .map(|pallet| PalletMetadata {
+            name: pallet.name,
+            storage: pallet.storage,
+            calls: pallet.calls,
+            event: pallet.event,
+            constants: pallet.constants,
+            error: pallet.error,
+            index: pallet.index,
+            docs: vec![],
+        })
+        .collect();
+
+    let signed_extensions = extrinsic
+        .signed_extensions
+        .into_iter()
+        .map(|ext| SignedExtensionMetadata {
+            identifier: ext.identifier,
+            ty: ext.ty,
+            additional_signed: ext.additional_signed,
+        })
+        .collect();
+
+    Ok(RuntimeMetadataLastVersion {
+        types,
+        pallets,
+        extrinsic: ExtrinsicMetadata {
+            version: extrinsic.version,
+            address_ty,
+            call_ty,
+            signature_ty,
+            extra_ty,
+            signed_extensions,
+        },
+        ty,
+        apis: vec![],
+        outer_enums: OuterEnums {
+            call_enum_ty: call_ty,
+            event_enum_ty,
+            error_enum_ty,
+        },
+        custom: CustomMetadata {
+            map: BTreeMap::new(),
+        },
+    })
+}
+
+/// The outer event enum is the `E` type parameter of the `EventRecord`s stored in `System.Events`.
+fn find_event_enum(
+    types: &PortableRegistry,
+    pallets: &[v14::PalletMetadata<PortableForm>],
+) -> Result<TypeSymbol, InvalidMetadataError> {
+    let events_entry = pallets
+        .iter()
+        .find(|pallet| pallet.name == "System")
+        .and_then(|pallet| pallet.storage.as_ref())
+        .and_then(|storage| {
+            storage.entries.iter().find(|entry| entry.name == "Events")
+        });
+
+    let from_event_record = events_entry
+        .and_then(|entry| match &entry.ty {
+            StorageEntryType::Plain(ty) => types.resolve(ty.id),
+            _ => None,
+        })
+        .and_then(|ty| match &ty.type_def {
+            TypeDef::Sequence(seq) => types.resolve(seq.type_param.id),
+            _ => None,
+        })
+        .and_then(|record| {
+            record
+                .type_params
+                .iter()
+                .find(|param| param.name == "E")
+                .and_then(|param| param.ty)
+        });
+
+    from_event_record
+        .or_else(|| {
+            types
+                .types
+                .iter()
+                .find(|ty| {
+                    ty.ty.path.segments.last().map(String::as_str)
+                        == Some("RuntimeEvent")
+                })
+                .map(|ty| ty.id.into())
+        })
+        .ok_or_else(|| {
+            InvalidMetadataError::MissingOuterEnum("RuntimeEvent".to_string())
+        })
+}
+
+/// Append a type to an existing registry, returning its id.
+fn push_type(
+    types: &mut PortableRegistry,
+    ty: Type<PortableForm>,
+) -> TypeSymbol {
+    let id = types.types.len() as u32;
+    types.types.push(PortableType { id, ty });
+    id.into()
+}
+
+fn unnamed_field(
+    ty: TypeSymbol,
+    type_name: Option<String>,
+) -> Field<PortableForm> {
+    Field {
+        name: None,
+        ty,
+        type_name,
+        docs: vec![],
+    }
+}
+
+/// Upgrade V12 metadata to V15.
+///
+/// V13 only added the `NMap` storage entry type, so an encoded V12 metadata is also a valid
+/// encoded V13 metadata.
+pub fn v12_to_v15(
+    metadata: RuntimeMetadataV12,
+) -> Result<RuntimeMetadataLastVersion, InvalidMetadataError> {
+    let encoded = metadata.encode();
+    let metadata = RuntimeMetadataV13::decode(&mut encoded.as_slice())
+        .map_err(|_| InvalidMetadataError::InvalidLegacyMetadata)?;
+    v13_to_v15(metadata)
+}
+
+/// Upgrade V13 metadata to V15.
+///
+/// Legacy metadata describes types only by their rust names, so the type registry of the
+/// result holds an opaque placeholder for each distinct type name. Calls, events, errors,
+/// constants and storage entries keep their names, indices, hashers and documentation, but
+/// values of these placeholder types can not be encoded or decoded.
+pub fn v13_to_v15(
+    metadata: RuntimeMetadataV13,
+) -> Result<RuntimeMetadataLastVersion, InvalidMetadataError> {
+    let mut types = LegacyTypes::default();
+    let mut pallets = vec![];
+    let mut call_variants = vec![];
+    let mut event_variants = vec![];
+    let mut error_variants = vec![];
+
+    for module in decoded(metadata.modules)? {
+        let name = decoded(module.name)?;
+
+        let storage = match module.storage {
+            Some(storage) => {
+                let storage = decoded(storage)?;
+                let entries = decoded(storage.entries)?
+                    .into_iter()
+                    .map(|entry| types.storage_entry(entry))
+                    .collect::<Result<_, _>>()?;
+                Some(PalletStorageMetadata {
+                    prefix: decoded(storage.prefix)?,
+                    entries,
+                })
+            }
+            None => None,
+        };
+
+        let calls = match module.calls {
+            Some(calls) => {
+                let mut variants = vec![];
+                for (index, call) in decoded(calls)?.into_iter().enumerate() {
+                    let mut fields = vec![];
+                    for arg in decoded(call.arguments)? {
+                        let type_name = decoded(arg.ty)?;
+                        fields.push(Field {
+                            name: Some(decoded(arg.name)?),
+                            ty: types.named(&type_name),
+                            type_name: Some(type_name),
+                            docs: vec![],
+                        });
+                    }
+                    variants.push(Variant {
+                        name: decoded(call.name)?,
+                        fields,
+                        index: index as u8,
+                        docs: decoded(call.documentation)?,
+                    });
+                }
+                let ty = types.pallet_enum(&name, "Call", variants);
+                call_variants.push(outer_variant(&name, module.index, ty));
+                Some(PalletCallMetadata { ty })
+            }
+            None => None,
+        };
+
+        let event = match module.event {
+            Some(events) => {
+                let mut variants = vec![];
+                for (index, event) in decoded(events)?.into_iter().enumerate() {
+                    let fields = decoded(event.arguments)?
+                        .into_iter()
+                        .map(|type_name| {
+                            unnamed_field(
+                                types.named(&type_name),
+                                Some(type_name),
+                            )
+                        })
+                        .collect();
+                    variants.push(Variant {
+                        name: decoded(event.name)?,
+                        fields,
+                        index: index as u8,
+                        docs: decoded(event.documentation)?,
+                    });
+                }
+                let ty = types.pallet_enum(&name, "Event", variants);
+                event_variants.push(outer_variant(&name, module.index, ty));
+                Some(PalletEventMetadata { ty })
+            }
+            None => None,
+        };
+
+        let mut constants = vec![];
+        for constant in decoded(module.constants)? {
+            let type_name = decoded(constant.ty)?;
+            constants.push(PalletConstantMetadata {
+                name: decoded(constant.name)?,
+                ty: types.named(&type_name),
+                value: decoded(constant.value)?,
+                docs: decoded(constant.documentation)?,
+            });
+        }
+
+        let errors = decoded(module.errors)?;
+        let error = if errors.is_empty() {
+            None
+        } else {
+            let mut variants = vec![];
+            for (index, error) in errors.into_iter().enumerate() {
+                variants.push(Variant {
+                    name: decoded(error.name)?,
+                    fields: vec![],
+                    index: index as u8,
+                    docs: decoded(error.documentation)?,
+                });
+            }
+            let ty = types.pallet_enum(&name, "Error", variants);
+            error_variants.push(outer_variant(&name, module.index, ty));
+            Some(PalletErrorMetadata { ty })
+        };
+
+        pallets.push(PalletMetadata {
+            name,
+            storage,
+            calls,
+            event,
+            constants,
+            error,
+            index: module.index,
+            docs: vec![],
+        });
+    }
+
+    let call_enum_ty = types.outer_enum("RuntimeCall", call_variants);
+    let event_enum_ty = types.outer_enum("RuntimeEvent", event_variants);
+    let error_enum_ty = types.outer_enum("RuntimeError", error_variants);
+
+    let mut signed_extensions = vec![];
+    for identifier in metadata.extrinsic.signed_extensions {
+        let identifier = decoded(identifier)?;
+        signed_extensions.push(SignedExtensionMetadata {
+            ty: types.named(&identifier),
+            additional_signed: types
+                .named(&format!("{}::AdditionalSigned", identifier)),
+            identifier,
+        });
+    }
+    let extrinsic = ExtrinsicMetadata {
+        version: metadata.extrinsic.version,
+        address_ty: types.named("Address"),
+        call_ty: call_enum_ty,
+        signature_ty: types.named("Signature"),
+        extra_ty: types.named("Extra"),
+        signed_extensions,
+    };
+    let ty = types.named("Runtime");
+
+    Ok(RuntimeMetadataLastVersion {
+        types: types.builder.finish(),
+        pallets,
+        extrinsic,
+        ty,
+        apis: vec![],
+        outer_enums: OuterEnums {
+            call_enum_ty,
+            event_enum_ty,
+            error_enum_ty,
+        },
+        custom: CustomMetadata {
+            map: BTreeMap::new(),
+        },
+    })
+}
+
+fn outer_variant(
+    pallet: &str,
+    index: u8,
+    ty: TypeSymbol,
+) -> Variant<PortableForm> {
+    Variant {
+        name: pallet.to_string(),
+        fields: vec![unnamed_field(ty, None)],
+        index,
+        docs: vec![],
+    }
+}
+
+/// Unwrap a value decoded from legacy metadata.
+fn decoded<B, O>(
+    value: DecodeDifferent<B, O>,
+) -> Result<O, InvalidMetadataError> {
+    match value {
+        DecodeDifferent::Decoded(value) => Ok(value),
+        DecodeDifferent::Encode(_) => {
+            Err(InvalidMetadataError::InvalidLegacyMetadata)
+        }
+    }
+}
+
+/// Builds the type registry for legacy metadata.
+#[derive(Default)]
+struct LegacyTypes {
+    builder: PortableRegistryBuilder,
+}
+
+impl LegacyTypes {
+    /// Register an opaque placeholder for the legacy type `name`.
+    ///
+    /// The registry deduplicates identical types, so every occurrence of the same name
+    /// resolves to the same id.
+    fn named(&mut self, name: &str) -> TypeSymbol {
+        self.register(Type {
+            path: Path {
+                segments: vec![name.to_string()],
+            },
+            type_params: vec![],
+            type_def: TypeDef::Composite(TypeDefComposite { fields: vec![] }),
+            docs: vec![],
+        })
+    }
+
+    fn pallet_enum(
+        &mut self,
+        pallet: &str,
+        name: &str,
+        variants: Vec<Variant<PortableForm>>,
+    ) -> TypeSymbol {
+        self.register(Type {
+            path: Path {
+                segments: vec![pallet.to_string(), name.to_string()],
+            },
+            type_params: vec![],
+            type_def: TypeDef::Variant(TypeDefVariant { variants }),
+            docs: vec![],
+        })
+    }
+
+    fn outer_enum(
+        &mut self,
+        name: &str,
+        variants: Vec<Variant<PortableForm>>,
+    ) -> TypeSymbol {
+        self.register(Type {
+            path: Path {
+                segments: vec![name.to_string()],
+            },
+            type_params: vec![],
+            type_def: TypeDef::Variant(TypeDefVariant { variants }),
+            docs: vec![],
+        })
+    }
+
+    fn tuple(&mut self, fields: Vec<TypeSymbol>) -> TypeSymbol {
+        self.register(Type {
+            path: Path::default(),
+            type_params: vec![],
+            type_def: TypeDef::Tuple(TypeDefTuple { fields }),
+            docs: vec![],
+        })
+    }
+
+    fn register(&mut self, ty: Type<PortableForm>) -> TypeSymbol {
+        self.builder.register_type(ty).into()
+    }
+
+    /// Convert a legacy storage entry, multi key maps get a tuple key type.
+    fn storage_entry(
+        &mut self,
+        entry: v13::StorageEntryMetadata,
+    ) -> Result<StorageEntryMetadata<PortableForm>, InvalidMetadataError> {
+        let ty = match entry.ty {
+            v13::StorageEntryType::Plain(value) => {
+                StorageEntryType::Plain(self.named(&decoded(value)?))
+            }
+            v13::StorageEntryType::Map {
+                hasher, key, value, ..
+            } => StorageEntryType::Map {
+                hashers: vec![storage_hasher(&hasher)],
+                key: self.named(&decoded(key)?),
+                value: self.named(&decoded(value)?),
+            },
+            v13::StorageEntryType::DoubleMap {
+                hasher,
+                key1,
+                key2,
+                value,
+                key2_hasher,
+            } => {
+                let keys = vec![
+                    self.named(&decoded(key1)?),
+                    self.named(&decoded(key2)?),
+                ];
+                StorageEntryType::Map {
+                    hashers: vec![
+                        storage_hasher(&hasher),
+                        storage_hasher(&key2_hasher),
+                    ],
+                    key: self.tuple(keys),
+                    value: self.named(&decoded(value)?),
+                }
+            }
+            v13::StorageEntryType::NMap {
+                keys,
+                hashers,
+                value,
+            } => {
+                let keys =
+                    decoded(keys)?.iter().map(|key| self.named(key)).collect();
+                StorageEntryType::Map {
+                    hashers: decoded(hashers)?
+                        .iter()
+                        .map(storage_hasher)
+                        .collect(),
+                    key: self.tuple(keys),
+                    value: self.named(&decoded(value)?),
+                }
+            }
+        };
+
+        Ok(StorageEntryMetadata {
+            name: decoded(entry.name)?,
+            modifier: match entry.modifier {
+                v13::StorageEntryModifier::Optional => {
+                    StorageEntryModifier::Optional
+                }
+                v13::StorageEntryModifier::Default => {
+                    StorageEntryModifier::Default
+                }
+            },
+            ty,
+            default: decoded(entry.default)?,
+            docs: decoded(entry.documentation)?,
+        })
+    }
+}
+
+fn storage_hasher(hasher: &v13::StorageHasher) -> StorageHasher {
+    match hasher {
+        v13::StorageHasher::Blake2_128 => StorageHasher::Blake2_128,
+        v13::StorageHasher::Blake2_256 => StorageHasher::Blake2_256,
+        v13::StorageHasher::Blake2_128Concat => StorageHasher::Blake2_128Concat,
+        v13::StorageHasher::Twox128 => StorageHasher::Twox128,
+        v13::StorageHasher::Twox256 => StorageHasher::Twox256,
+        v13::StorageHasher::Twox64Concat => StorageHasher::Twox64Concat,
+        v13::StorageHasher::Identity => StorageHasher::Identity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::metadata::Metadata;
+    use frame_metadata::{
+        v13::{
+            ErrorMetadata, EventMetadata, ExtrinsicMetadata,
+            FunctionArgumentMetadata, FunctionMetadata, ModuleMetadata,
+            StorageMetadata,
+        },
+        RuntimeMetadata, RuntimeMetadataPrefixed, META_RESERVED,
+    };
+
+    fn d<T>(value: T) -> DecodeDifferent<&'static str, T> {
+        DecodeDifferent::Decoded(value)
+    }
+
+    fn balances_module() -> ModuleMetadata {
+        ModuleMetadata {
+            name: d("Balances".to_string()),
+            storage: Some(DecodeDifferent::Decoded(StorageMetadata {
+                prefix: d("Balances".to_string()),
+                entries: DecodeDifferent::Decoded(vec![
+                    v13::StorageEntryMetadata {
+                        name: d("Account".to_string()),
+                        modifier: v13::StorageEntryModifier::Default,
+                        ty: v13::StorageEntryType::Map {
+                            hasher: v13::StorageHasher::Blake2_128Concat,
+                            key: d("T::AccountId".to_string()),
+                            value: d("AccountData<T::Balance>".to_string()),
+                            unused: false,
+                        },
+                        default: DecodeDifferent::Decoded(vec![0; 64]),
+                        documentation: DecodeDifferent::Decoded(vec![]),
+                    },
+                ]),
+            })),
+            calls: Some(DecodeDifferent::Decoded(vec![FunctionMetadata {
+                name: d("transfer".to_string()),
+                arguments: DecodeDifferent::Decoded(vec![
+                    FunctionArgumentMetadata {
+                        name: d("dest".to_string()),
+                        ty: d("<T::Lookup as StaticLookup>::Source".to_string()),
+                    },
+                    FunctionArgumentMetadata {
+                        name: d("value".to_string()),
+                        ty: d("Compact<T::Balance>".to_string()),
+                    },
+                ]),
+                documentation: DecodeDifferent::Decoded(vec![]),
+            }])),
+            event: Some(DecodeDifferent::Decoded(vec![EventMetadata {
+                name: d("Transfer".to_string()),
+                arguments: DecodeDifferent::Decoded(vec![
+                    "AccountId".to_string(),
+                    "AccountId".to_string(),
+                    "Balance".to_string(),
+                ]),
+                documentation: DecodeDifferent::Decoded(vec![]),
+            }])),
+            constants: DecodeDifferent::Decoded(vec![]),
+            errors: DecodeDifferent::Decoded(vec![ErrorMetadata {
+                name: d("InsufficientBalance".to_string()),
+                documentation: DecodeDifferent::Decoded(vec![
+                    "Balance too low to send value".to_string(),
+                ]),
+            }]),
+            index: 5,
+        }
+    }
+
+    #[test]
+    fn test_v13_to_v15() {
+        let v13 = RuntimeMetadataV13 {
+            modules: DecodeDifferent::Decoded(vec![balances_module()]),
+            extrinsic: ExtrinsicMetadata {
+                version: 4,
+                signed_extensions: vec![d("CheckNonce".to_string())],
+            },
+        };
+        // round trip through SCALE, as if the metadata came from the chain
+        let encoded =
+            RuntimeMetadataPrefixed(META_RESERVED, RuntimeMetadata::V13(v13))
+                .encode();
+        let prefixed =
+            RuntimeMetadataPrefixed::decode(&mut encoded.as_slice()).unwrap();
+        let metadata = Metadata::try_from(prefixed).unwrap();
+
+        assert_eq!(metadata.version, 13);
+        assert!(!metadata.has_type_registry());
+        assert_eq!(
+            metadata.pallet_call_index("Balances", "transfer").unwrap(),
+            [5, 0]
+        );
+        assert_eq!(metadata.get_event(5, 0).unwrap().event(), "Transfer");
+        assert_eq!(
+            metadata.get_error(5, 0).unwrap().description(),
+            ["Balance too low to send value".to_string()]
+        );
+        let account = metadata.pallet("Balances").unwrap().storage("Account");
+        match &account.unwrap().ty {
+            StorageEntryType::Map { hashers, .. } => {
+                assert_eq!(hashers, &[StorageHasher::Blake2_128Concat])
+            }
+            _ => panic!("expecting a map"),
+        }
+        assert_eq!(
+            metadata.metadata.extrinsic.signed_extensions[0].identifier,
+            "CheckNonce"
+        );
+    }
+}
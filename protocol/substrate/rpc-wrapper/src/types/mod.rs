@@ -1,5 +1,17 @@
 pub mod account_info;
+pub mod block;
+pub mod child_storage;
+pub mod decode;
+pub mod dispatch_error;
+pub mod encode;
+pub mod events;
 pub mod extrinsic;
 pub mod extrinsic_params;
 pub mod metadata;
+pub mod metadata_diff;
+pub mod metadata_info;
+pub mod metadata_versions;
+pub mod read_proof;
 pub mod storage;
+#[cfg(test)]
+pub mod test_metadata;
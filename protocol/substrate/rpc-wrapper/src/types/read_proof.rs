@@ -0,0 +1,121 @@
+//! Verify the storage proofs returned by the `state_getReadProof` RPC.
+//!
+//! The state is a base-16 Patricia-Merkle trie hashed with blake2_256. Its nodes are the
+//! same in both state versions, version 1 only storing the values longer than 32 bytes
+//! in their own node, referenced by their hash.
+//!
+//! This file is **not** from subxt.
+
+use sp_core::{storage::StorageKey, H256};
+use sp_runtime::traits::BlakeTwo256;
+use sp_trie::{read_trie_value, LayoutV0, LayoutV1, StorageProof};
+
+/// Error when the proof doesn't prove the value of a key against the state root.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid read proof for key {key}: {reason}")]
+pub struct ReadProofError {
+    /// Hex of the storage key.
+    pub key: String,
+    pub reason: String,
+}
+
+/// Read the values at `keys` from the trie nodes of `proof`, checking that each node on the
+/// way is part of the state with root `state_root`. A `None` value is a proven absence.
+pub fn verify_read_proof(
+    state_root: H256,
+    state_version: u8,
+    proof: Vec<Vec<u8>>,
+    keys: &[StorageKey],
+) -> Result<Vec<Option<Vec<u8>>>, ReadProofError> {
+    let db = StorageProof::new(proof).into_memory_db::<BlakeTwo256>();
+    keys.iter()
+        .map(|key| {
+            let value = match state_version {
+                0 => read_trie_value::<LayoutV0<BlakeTwo256>, _>(
+                    &db,
+                    &state_root,
+                    &key.0,
+                    None,
+                    None,
+                ),
+                _ => read_trie_value::<LayoutV1<BlakeTwo256>, _>(
+                    &db,
+                    &state_root,
+                    &key.0,
+                    None,
+                    None,
+                ),
+            };
+            value.map_err(|err| ReadProofError {
+                key: format!("0x{}", hex::encode(&key.0)),
+                reason: format!("{:?}", err),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_trie::{MemoryDB, TrieDBMutBuilder, TrieLayout, TrieMut};
+
+    /// Build a trie of `entries` and return its root with all its nodes as the proof.
+    fn trie_with_proof<L: TrieLayout<Hash = BlakeTwo256>>(
+        entries: &[(&[u8], Vec<u8>)],
+    ) -> (H256, Vec<Vec<u8>>) {
+        let mut db = MemoryDB::<BlakeTwo256>::default();
+        let mut root = H256::default();
+        {
+            let mut trie =
+                TrieDBMutBuilder::<L>::new(&mut db, &mut root).build();
+            for (key, value) in entries {
+                trie.insert(key, value).unwrap();
+            }
+        }
+        let proof = db
+            .drain()
+            .into_values()
+            .filter(|(_, rc)| *rc > 0)
+            .map(|(node, _)| node)
+            .collect();
+        (root, proof)
+    }
+
+    #[test]
+    fn test_verify_read_proof() {
+        let long_value = vec![7u8; 64];
+        let entries: [(&[u8], Vec<u8>); 3] = [
+            (b"alice", vec![1, 2, 3]),
+            (b"alicia", long_value.clone()),
+            (b"bob", vec![4]),
+        ];
+        let keys = [
+            StorageKey(b"alice".to_vec()),
+            StorageKey(b"alicia".to_vec()),
+            StorageKey(b"carol".to_vec()),
+        ];
+        for state_version in [0, 1] {
+            let (root, proof) = match state_version {
+                0 => trie_with_proof::<LayoutV0<BlakeTwo256>>(&entries),
+                _ => trie_with_proof::<LayoutV1<BlakeTwo256>>(&entries),
+            };
+            assert_eq!(
+                verify_read_proof(root, state_version, proof.clone(), &keys)
+                    .unwrap(),
+                vec![Some(vec![1, 2, 3]), Some(long_value.clone()), None]
+            );
+            // the nodes don't prove anything against another root
+            assert!(verify_read_proof(
+                H256::repeat_byte(1),
+                state_version,
+                proof,
+                &keys
+            )
+            .is_err());
+            // nor with a node missing
+            assert!(
+                verify_read_proof(root, state_version, vec![], &keys).is_err()
+            );
+        }
+    }
+}
@@ -2,13 +2,22 @@
 //!
 //! This file is **not** from subxt.
 
-use crate::types::metadata::MetadataError;
+use crate::types::{
+    decode::{self, DecodeError},
+    metadata::MetadataError,
+};
 use codec::Encode;
-use frame_metadata::{StorageEntryMetadata, StorageEntryType, StorageHasher};
-use scale_info::form::PortableForm;
+use frame_metadata::v14::{
+    StorageEntryMetadata, StorageEntryType, StorageHasher,
+};
+use scale_info::{form::PortableForm, PortableRegistry};
+use serde_json::Value;
 use sp_core::storage::StorageKey;
 use std::marker::PhantomData;
 
+/// Length of the `twox_128` hashes of the pallet and storage names starting every key.
+pub const PREFIX_LEN: usize = 32;
+
 #[derive(Clone, Debug)]
 pub struct StorageValue {
     module_prefix: Vec<u8>,
@@ -60,6 +69,32 @@ impl<K: Encode, Q: Encode> StorageDoubleMap<K, Q> {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct StorageNMap {
+    module_prefix: Vec<u8>,
+    storage_prefix: Vec<u8>,
+    hashers: Vec<StorageHasher>,
+}
+
+impl StorageNMap {
+    /// Build the key from the SCALE encoded `keys`, each hashed with its own hasher.
+    /// Passing fewer keys than hashers gives the prefix of all the entries sharing those keys.
+    pub fn key(&self, keys: &[Vec<u8>]) -> Result<StorageKey, MetadataError> {
+        if keys.len() > self.hashers.len() {
+            return Err(MetadataError::StorageKeyCount(
+                self.hashers.len(),
+                keys.len(),
+            ));
+        }
+        let mut bytes = sp_core::twox_128(&self.module_prefix).to_vec();
+        bytes.extend(&sp_core::twox_128(&self.storage_prefix)[..]);
+        for (key, hasher) in keys.iter().zip(&self.hashers) {
+            bytes.extend(hash_encoded(key, hasher));
+        }
+        Ok(StorageKey(bytes))
+    }
+}
+
 /// trait to extract the storage based on the [`StorageEntryMetadata`].
 pub trait GetStorage {
     fn get_double_map<K: Encode, Q: Encode>(
@@ -74,6 +109,10 @@ pub trait GetStorage {
         &self,
         pallet_prefix: &str,
     ) -> Result<StorageKey, MetadataError>;
+    fn get_n_map(
+        &self,
+        pallet_prefix: &str,
+    ) -> Result<StorageNMap, MetadataError>;
     fn get_value(
         &self,
         pallet_prefix: &str,
@@ -90,7 +129,7 @@ impl GetStorage for StorageEntryMetadata<PortableForm> {
                 let module_prefix = pallet_prefix.as_bytes().to_vec();
                 let storage_prefix = self.name.as_bytes().to_vec();
                 let hasher1 =
-                    hashers.get(0).ok_or(MetadataError::StorageTypeError)?;
+                    hashers.first().ok_or(MetadataError::StorageTypeError)?;
                 let hasher2 =
                     hashers.get(1).ok_or(MetadataError::StorageTypeError)?;
 
@@ -122,7 +161,7 @@ impl GetStorage for StorageEntryMetadata<PortableForm> {
         match &self.ty {
             StorageEntryType::Map { hashers, .. } => {
                 let hasher = hashers
-                    .get(0)
+                    .first()
                     .ok_or(MetadataError::StorageTypeError)?
                     .to_owned();
 
@@ -161,6 +200,28 @@ impl GetStorage for StorageEntryMetadata<PortableForm> {
         }
     }
 
+    fn get_n_map(
+        &self,
+        pallet_prefix: &str,
+    ) -> Result<StorageNMap, MetadataError> {
+        match &self.ty {
+            StorageEntryType::Map { hashers, .. } => {
+                log::debug!(
+                    "map for '{}' '{}' has hashers {:?}",
+                    pallet_prefix,
+                    self.name,
+                    hashers
+                );
+                Ok(StorageNMap {
+                    module_prefix: pallet_prefix.as_bytes().to_vec(),
+                    storage_prefix: self.name.as_bytes().to_vec(),
+                    hashers: hashers.to_owned(),
+                })
+            }
+            _ => Err(MetadataError::StorageTypeError),
+        }
+    }
+
     fn get_value(
         &self,
         pallet_prefix: &str,
@@ -181,26 +242,131 @@ impl GetStorage for StorageEntryMetadata<PortableForm> {
 
 /// generates the key's hash depending on the StorageHasher selected
 fn key_hash<K: Encode>(key: &K, hasher: &StorageHasher) -> Vec<u8> {
-    let encoded_key = key.encode();
+    hash_encoded(&key.encode(), hasher)
+}
+
+/// generates the hash of an already encoded key depending on the StorageHasher selected
+fn hash_encoded(encoded_key: &[u8], hasher: &StorageHasher) -> Vec<u8> {
     match hasher {
         StorageHasher::Identity => encoded_key.to_vec(),
-        StorageHasher::Blake2_128 => sp_core::blake2_128(&encoded_key).to_vec(),
+        StorageHasher::Blake2_128 => sp_core::blake2_128(encoded_key).to_vec(),
         StorageHasher::Blake2_128Concat => {
             // copied from substrate Blake2_128Concat::hash since StorageHasher is not public
-            let x: &[u8] = encoded_key.as_slice();
+            let x: &[u8] = encoded_key;
             sp_core::blake2_128(x)
                 .iter()
                 .chain(x.iter())
                 .cloned()
                 .collect::<Vec<_>>()
         }
-        StorageHasher::Blake2_256 => sp_core::blake2_256(&encoded_key).to_vec(),
-        StorageHasher::Twox128 => sp_core::twox_128(&encoded_key).to_vec(),
-        StorageHasher::Twox256 => sp_core::twox_256(&encoded_key).to_vec(),
-        StorageHasher::Twox64Concat => sp_core::twox_64(&encoded_key)
+        StorageHasher::Blake2_256 => sp_core::blake2_256(encoded_key).to_vec(),
+        StorageHasher::Twox128 => sp_core::twox_128(encoded_key).to_vec(),
+        StorageHasher::Twox256 => sp_core::twox_256(encoded_key).to_vec(),
+        StorageHasher::Twox64Concat => sp_core::twox_64(encoded_key)
             .iter()
-            .chain(&encoded_key)
+            .chain(encoded_key)
             .cloned()
             .collect(),
     }
 }
+
+/// the length of the hash the hasher puts before the key, which only follows the hash
+/// with `Identity` and the `*Concat` hashers.
+fn hash_len(hasher: &StorageHasher) -> usize {
+    match hasher {
+        StorageHasher::Identity => 0,
+        StorageHasher::Twox64Concat => 8,
+        StorageHasher::Blake2_128
+        | StorageHasher::Blake2_128Concat
+        | StorageHasher::Twox128 => 16,
+        StorageHasher::Blake2_256 | StorageHasher::Twox256 => 32,
+    }
+}
+
+/// Recover the keys of a map entry from its `storage_key`, made of the pallet and storage
+/// prefix followed by each hashed key. The keys hashed with `Identity` or a `*Concat`
+/// hasher are decoded with their type from `key_types`, the other keys can't be recovered
+/// from their hash and give `None`.
+pub fn decode_map_keys(
+    registry: &PortableRegistry,
+    hashers: &[StorageHasher],
+    key_types: &[u32],
+    storage_key: &[u8],
+) -> Result<Vec<Option<Value>>, DecodeError> {
+    let too_short = |index: usize| DecodeError {
+        path: format!("key {}", index),
+        reason: "storage key too short".to_string(),
+    };
+    let input =
+        &mut storage_key.get(PREFIX_LEN..).ok_or_else(|| too_short(0))?;
+    let mut keys = Vec::with_capacity(hashers.len());
+    for (index, (hasher, key_type)) in hashers.iter().zip(key_types).enumerate()
+    {
+        *input = input
+            .get(hash_len(hasher)..)
+            .ok_or_else(|| too_short(index))?;
+        match hasher {
+            StorageHasher::Identity
+            | StorageHasher::Blake2_128Concat
+            | StorageHasher::Twox64Concat => keys.push(Some(
+                decode::decode_value_from(registry, *key_type, input)?,
+            )),
+            _ => keys.push(None),
+        }
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale_info::{meta_type, Registry};
+    use serde_json::json;
+
+    #[test]
+    fn test_decode_map_keys() {
+        let mut registry = Registry::new();
+        let u32_id = registry.register_type(&meta_type::<u32>()).id;
+        let u64_id = registry.register_type(&meta_type::<u64>()).id;
+        let registry: PortableRegistry = registry.into();
+
+        let map = StorageNMap {
+            module_prefix: b"Assets".to_vec(),
+            storage_prefix: b"Account".to_vec(),
+            hashers: vec![
+                StorageHasher::Twox64Concat,
+                StorageHasher::Blake2_128,
+                StorageHasher::Identity,
+                StorageHasher::Blake2_128Concat,
+            ],
+        };
+        let keys = [
+            7u32.encode(),
+            8u32.encode(),
+            9u64.encode(),
+            u64::MAX.encode(),
+        ];
+        let storage_key = map.key(&keys).unwrap();
+        let key_types = [u32_id, u32_id, u64_id, u64_id];
+
+        assert_eq!(
+            decode_map_keys(
+                &registry,
+                &map.hashers,
+                &key_types,
+                &storage_key.0
+            )
+            .unwrap(),
+            vec![Some(json!(7)), None, Some(json!(9)), Some(json!(u64::MAX))]
+        );
+        // a prefix of the keys can't be decoded as the full keys
+        let prefix = map.key(&keys[..2]).unwrap();
+        assert!(decode_map_keys(
+            &registry,
+            &map.hashers,
+            &key_types,
+            &prefix.0
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,254 @@
+//! V14 metadata of a minimal runtime with the `System` and `Balances` pallets, for the
+//! unit tests which need a type registry.
+//!
+//! This file is **not** from subxt.
+
+// most types only describe the runtime through their `TypeInfo`
+#![allow(dead_code)]
+
+use crate::Metadata;
+use codec::{Compact, Encode};
+use frame_metadata::{
+    v14::{
+        ExtrinsicMetadata, PalletCallMetadata, PalletErrorMetadata,
+        PalletEventMetadata, PalletMetadata, PalletStorageMetadata,
+        RuntimeMetadataV14, SignedExtensionMetadata, StorageEntryMetadata,
+        StorageEntryModifier, StorageEntryType, StorageHasher,
+    },
+    RuntimeMetadata, RuntimeMetadataPrefixed, META_RESERVED,
+};
+use scale_info::{meta_type, TypeInfo};
+use sp_core::{ecdsa, H256};
+use sp_runtime::{
+    generic::Era, AccountId32, DispatchError, MultiAddress, MultiSignature,
+};
+use std::{convert::TryFrom, marker::PhantomData};
+
+/// The 20 bytes account of Ethereum compatible chains.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, TypeInfo)]
+pub struct AccountId20(pub [u8; 20]);
+
+/// The signature of the accounts of Ethereum compatible chains.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, TypeInfo)]
+pub struct EthereumSignature(pub ecdsa::Signature);
+
+#[derive(TypeInfo)]
+struct UncheckedExtrinsic<Address, Call, Signature, Extra>(
+    PhantomData<(Address, Call, Signature, Extra)>,
+);
+
+#[allow(non_camel_case_types)]
+#[derive(Encode, TypeInfo)]
+pub enum BalancesCall<Address> {
+    #[codec(index = 0)]
+    transfer {
+        dest: Address,
+        #[codec(compact)]
+        value: u128,
+    },
+}
+
+#[derive(Encode, TypeInfo)]
+pub enum RuntimeCall<Address> {
+    #[codec(index = 5)]
+    Balances(BalancesCall<Address>),
+}
+
+#[derive(Encode, TypeInfo)]
+pub enum SystemEvent {
+    ExtrinsicSuccess,
+    ExtrinsicFailed { dispatch_error: DispatchError },
+    CodeUpdated,
+}
+
+#[derive(Encode, TypeInfo)]
+pub enum BalancesEvent<AccountId> {
+    #[codec(index = 2)]
+    Transfer {
+        from: AccountId,
+        to: AccountId,
+        amount: u128,
+    },
+}
+
+#[derive(Encode, TypeInfo)]
+pub enum RuntimeEvent<AccountId> {
+    #[codec(index = 0)]
+    System(SystemEvent),
+    #[codec(index = 5)]
+    Balances(BalancesEvent<AccountId>),
+}
+
+#[derive(Encode, TypeInfo)]
+pub enum Phase {
+    ApplyExtrinsic(u32),
+    Finalization,
+    Initialization,
+}
+
+#[derive(Encode, TypeInfo)]
+pub struct EventRecord<E> {
+    pub phase: Phase,
+    pub event: E,
+    pub topics: Vec<H256>,
+}
+
+#[derive(TypeInfo)]
+pub enum BalancesError {
+    /// Vesting balance too high to send value.
+    VestingBalance,
+    /// Balance too low to send value.
+    InsufficientBalance,
+}
+
+#[derive(TypeInfo)]
+struct AccountInfo {
+    nonce: u32,
+    consumers: u32,
+    providers: u32,
+    sufficients: u32,
+}
+
+#[derive(TypeInfo)]
+struct ChargeAssetTxPayment {
+    #[codec(compact)]
+    tip: u128,
+    asset_id: Option<u32>,
+}
+
+type Extra = (Era, Compact<u32>, ChargeAssetTxPayment);
+
+/// The metadata of a runtime with `MultiAddress` addresses and `MultiSignature`
+/// signatures, as on polkadot.
+pub fn metadata() -> Metadata {
+    metadata_with::<MultiAddress<AccountId32, u32>, AccountId32, MultiSignature>(
+    )
+}
+
+/// The metadata of a runtime with the `Address`, `AccountId` and `Signature` types.
+pub fn metadata_with<Address, AccountId, Signature>() -> Metadata
+where
+    Address: TypeInfo + 'static,
+    AccountId: TypeInfo + 'static,
+    Signature: TypeInfo + 'static,
+{
+    runtime_metadata::<Address, AccountId, Signature>(vec![])
+}
+
+/// The metadata of the polkadot like runtime of [`metadata`] with the extra `pallets`.
+pub fn metadata_with_pallets(pallets: Vec<PalletMetadata>) -> Metadata {
+    runtime_metadata::<
+        MultiAddress<AccountId32, u32>,
+        AccountId32,
+        MultiSignature,
+    >(pallets)
+}
+
+fn runtime_metadata<Address, AccountId, Signature>(
+    pallets: Vec<PalletMetadata>,
+) -> Metadata
+where
+    Address: TypeInfo + 'static,
+    AccountId: TypeInfo + 'static,
+    Signature: TypeInfo + 'static,
+{
+    let system = PalletMetadata {
+        name: "System",
+        storage: Some(PalletStorageMetadata {
+            prefix: "System",
+            entries: vec![
+                StorageEntryMetadata {
+                    name: "Account",
+                    modifier: StorageEntryModifier::Default,
+                    ty: StorageEntryType::Map {
+                        hashers: vec![StorageHasher::Blake2_128Concat],
+                        key: meta_type::<AccountId>(),
+                        value: meta_type::<AccountInfo>(),
+                    },
+                    default: vec![0; 16],
+                    docs: vec![],
+                },
+                StorageEntryMetadata {
+                    name: "Events",
+                    modifier: StorageEntryModifier::Default,
+                    ty: StorageEntryType::Plain(meta_type::<
+                        Vec<EventRecord<RuntimeEvent<AccountId>>>,
+                    >()),
+                    default: vec![0],
+                    docs: vec![],
+                },
+            ],
+        }),
+        calls: None,
+        event: Some(PalletEventMetadata {
+            ty: meta_type::<SystemEvent>(),
+        }),
+        constants: vec![],
+        error: None,
+        index: 0,
+    };
+    let balances = PalletMetadata {
+        name: "Balances",
+        storage: None,
+        calls: Some(PalletCallMetadata {
+            ty: meta_type::<BalancesCall<Address>>(),
+        }),
+        event: Some(PalletEventMetadata {
+            ty: meta_type::<BalancesEvent<AccountId>>(),
+        }),
+        constants: vec![],
+        error: Some(PalletErrorMetadata {
+            ty: meta_type::<BalancesError>(),
+        }),
+        index: 5,
+    };
+
+    let extension =
+        |identifier, ty, additional_signed| SignedExtensionMetadata {
+            identifier,
+            ty,
+            additional_signed,
+        };
+    let extrinsic = ExtrinsicMetadata {
+        ty: meta_type::<
+            UncheckedExtrinsic<Address, RuntimeCall<Address>, Signature, Extra>,
+        >(),
+        version: 4,
+        signed_extensions: vec![
+            extension(
+                "CheckSpecVersion",
+                meta_type::<()>(),
+                meta_type::<u32>(),
+            ),
+            extension("CheckTxVersion", meta_type::<()>(), meta_type::<u32>()),
+            extension("CheckGenesis", meta_type::<()>(), meta_type::<H256>()),
+            extension(
+                "CheckMortality",
+                meta_type::<Era>(),
+                meta_type::<H256>(),
+            ),
+            extension(
+                "CheckNonce",
+                meta_type::<Compact<u32>>(),
+                meta_type::<()>(),
+            ),
+            extension("CheckWeight", meta_type::<()>(), meta_type::<()>()),
+            extension(
+                "ChargeAssetTxPayment",
+                meta_type::<ChargeAssetTxPayment>(),
+                meta_type::<()>(),
+            ),
+        ],
+    };
+
+    let v14 = RuntimeMetadataV14::new(
+        [vec![system, balances], pallets].concat(),
+        extrinsic,
+        meta_type::<()>(),
+    );
+    Metadata::try_from(RuntimeMetadataPrefixed(
+        META_RESERVED,
+        RuntimeMetadata::V14(v14),
+    ))
+    .unwrap()
+}
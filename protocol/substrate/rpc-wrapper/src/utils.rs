@@ -16,7 +16,7 @@ impl<S: AsRef<str>> FromHexStr<S> for Vec<u8> {
     fn from_hex(hex: S) -> Result<Self, hex::FromHexError> {
         let hexstr = hex.as_ref().trim_matches('\"').trim_start_matches("0x");
 
-        hex::decode(&hexstr)
+        hex::decode(hexstr)
     }
 }
 
@@ -47,6 +47,8 @@ export class KeyringSignerProvider implements SignerProvider {
         }
 
         return new Promise((resolve): void => {
+          // encode the signed extensions of the chain, as the polkadot.js extension does
+          this.registry.setSignedExtensions(payload.signedExtensions);
           const signed = this.registry.createType(
             'ExtrinsicPayload',
             payload,